@@ -0,0 +1,45 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields};
+
+#[proc_macro_derive(KindIndex)]
+pub fn derive_kind_index(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let Data::Enum(data) = &input.data else {
+        return Error::new_spanned(name, "KindIndex can only be derived for enums")
+            .to_compile_error()
+            .into();
+    };
+    if let Some(v) = data
+        .variants
+        .iter()
+        .find(|v| !matches!(v.fields, Fields::Unit))
+    {
+        return Error::new_spanned(v, "KindIndex requires fieldless variants")
+            .to_compile_error()
+            .into();
+    }
+
+    let count = data.variants.len();
+    let arms = data.variants.iter().enumerate().map(|(i, v)| {
+        let ident = &v.ident;
+        quote! { #name::#ident => #i }
+    });
+
+    quote! {
+        impl #impl_generics ::xlex_lexer::lexer::KindIndex for #name #ty_generics #where_clause {
+            const COUNT: usize = #count;
+
+            #[inline]
+            fn index(&self) -> usize {
+                match self {
+                    #(#arms,)*
+                }
+            }
+        }
+    }
+    .into()
+}
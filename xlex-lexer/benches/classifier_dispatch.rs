@@ -0,0 +1,28 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use xlex_lexer::lexer::{Config, DefaultClassifier, DynClassifier, LexerInline, NoCustom};
+
+fn bench_static_vs_dyn_classifier(c: &mut Criterion) {
+    let input = "hello 123 world! 💥 привет $S%^& asd\n".repeat(100_000);
+    let config = Config::default();
+    let classifier = DefaultClassifier;
+    let boxed: Box<dyn DynClassifier<NoCustom>> = Box::new(DefaultClassifier);
+
+    c.bench_function("classifier_static", |b| {
+        b.iter(|| {
+            let lexer = LexerInline::new(&config, &classifier, black_box(&input));
+
+            for _ in lexer {}
+        })
+    });
+    c.bench_function("classifier_dyn", |b| {
+        b.iter(|| {
+            let lexer = LexerInline::new(&config, &*boxed, black_box(&input));
+
+            for _ in lexer {}
+        })
+    });
+}
+
+criterion_group!(benches, bench_static_vs_dyn_classifier);
+criterion_main!(benches);
@@ -0,0 +1,580 @@
+use super::config::Config;
+use super::rules::Rule;
+use super::token::{BaseKind, Token, TokenKind};
+use super::trivia::Trivia;
+
+use std::collections::{HashSet, VecDeque};
+use std::{borrow::Cow, hash::Hash, mem, ops::Range};
+
+pub trait TokenIterExt<'a, TK>: Iterator<Item = Token<'a, TK>> + Sized
+where
+    TK: Copy + Eq + Hash,
+{
+    #[inline]
+    fn filter_kinds<IT>(self, kinds: IT) -> FilterKinds<Self, TK>
+    where
+        IT: IntoIterator<Item = TokenKind<TK>>,
+    {
+        FilterKinds {
+            iter: self,
+            kinds: kinds.into_iter().collect(),
+        }
+    }
+
+    #[inline]
+    fn map_text<F>(self, f: F) -> MapText<Self, F>
+    where
+        F: FnMut(Cow<'a, str>) -> Cow<'a, str>,
+    {
+        MapText { iter: self, f }
+    }
+
+    #[inline]
+    fn merge_adjacent<P>(self, pred: P) -> MergeAdjacent<'a, Self, TK, P>
+    where
+        P: FnMut(&Token<'a, TK>) -> bool,
+    {
+        MergeAdjacent {
+            iter: self,
+            pred,
+            pending: None,
+        }
+    }
+
+    #[inline]
+    fn split_on<P>(self, pred: P) -> SplitOn<'a, Self, TK, P>
+    where
+        P: FnMut(char) -> bool,
+    {
+        SplitOn {
+            iter: self,
+            pred,
+            current: None,
+            pieces: VecDeque::new(),
+            first: false,
+            carry: None,
+            held: None,
+        }
+    }
+
+    fn drop_trivia(self, config: &Config<TK>) -> DropTrivia<Self, TK> {
+        let comments = config.rules.iter().filter_map(|rule| match rule {
+            Rule::LineComment { kind, .. } | Rule::BlockComment { kind, .. } => Some(*kind),
+            _ => None,
+        });
+        DropTrivia {
+            iter: self,
+            comments: comments.collect(),
+        }
+    }
+}
+
+impl<'a, TK, I> TokenIterExt<'a, TK> for I
+where
+    I: Iterator<Item = Token<'a, TK>>,
+    TK: Copy + Eq + Hash,
+{
+}
+
+pub struct FilterKinds<I, TK: Copy + Eq + Hash> {
+    iter: I,
+    kinds: HashSet<TokenKind<TK>>,
+}
+
+impl<'a, I, TK> Iterator for FilterKinds<I, TK>
+where
+    I: Iterator<Item = Token<'a, TK>>,
+    TK: Copy + Eq + Hash,
+{
+    type Item = Token<'a, TK>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.find(|t| self.kinds.contains(&t.kind))
+    }
+}
+
+pub struct MapText<I, F> {
+    iter: I,
+    f: F,
+}
+
+impl<'a, I, TK, F> Iterator for MapText<I, F>
+where
+    I: Iterator<Item = Token<'a, TK>>,
+    TK: Copy + Eq + Hash,
+    F: FnMut(Cow<'a, str>) -> Cow<'a, str>,
+{
+    type Item = Token<'a, TK>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut token = self.iter.next()?;
+        token.text = (self.f)(token.text);
+        Some(token)
+    }
+}
+
+pub struct MergeAdjacent<'a, I, TK: Copy + Eq + Hash, P> {
+    iter: I,
+    pred: P,
+    pending: Option<Token<'a, TK>>,
+}
+
+impl<'a, I, TK, P> Iterator for MergeAdjacent<'a, I, TK, P>
+where
+    I: Iterator<Item = Token<'a, TK>>,
+    TK: Copy + Eq + Hash,
+    P: FnMut(&Token<'a, TK>) -> bool,
+{
+    type Item = Token<'a, TK>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut token = self.pending.take().or_else(|| self.iter.next())?;
+        if !(self.pred)(&token) {
+            return Some(token);
+        }
+
+        for next in self.iter.by_ref() {
+            if next.start != token.end || !(self.pred)(&next) {
+                self.pending = Some(next);
+                break;
+            }
+            if token.original.is_some() || next.original.is_some() {
+                let mut source = token.source().to_string();
+                source.push_str(next.source());
+                token.original = Some(Cow::Owned(source));
+            }
+            token.text.to_mut().push_str(&next.text);
+            token.end = next.end;
+            if let Some(trivia) = next.trivia {
+                let merged = token.trivia_mut();
+                merged.leading.extend(trivia.leading);
+                merged.trailing.extend(trivia.trailing);
+            }
+        }
+        Some(token)
+    }
+}
+
+pub struct SplitOn<'a, I, TK: Copy + Eq + Hash, P> {
+    iter: I,
+    pred: P,
+    current: Option<Token<'a, TK>>,
+    pieces: VecDeque<(Range<usize>, Range<usize>)>,
+    first: bool,
+    carry: Option<Box<Trivia<'a, TK>>>,
+    held: Option<Token<'a, TK>>,
+}
+
+impl<'a, I, TK, P> Iterator for SplitOn<'a, I, TK, P>
+where
+    I: Iterator<Item = Token<'a, TK>>,
+    TK: Copy + Eq + Hash,
+    P: FnMut(char) -> bool,
+{
+    type Item = Token<'a, TK>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut token = match self.held.take() {
+            Some(token) => token,
+            None => self.pull()?,
+        };
+        self.held = self.pull();
+        if let (None, Some(carry)) = (&self.held, self.carry.take()) {
+            token.trivia_mut().trailing.extend(carry.leading);
+        }
+        Some(token)
+    }
+}
+
+impl<'a, I, TK, P> SplitOn<'a, I, TK, P>
+where
+    I: Iterator<Item = Token<'a, TK>>,
+    TK: Copy + Eq + Hash,
+    P: FnMut(char) -> bool,
+{
+    fn pull(&mut self) -> Option<Token<'a, TK>> {
+        loop {
+            if let Some((text, source)) = self.pieces.pop_front() {
+                let token = self.current.as_mut().unwrap();
+                let mut piece = Token::new(
+                    token.kind,
+                    slice(&token.text, text),
+                    token.start + source.start,
+                    token.start + source.end,
+                );
+                if let Some(original) = &token.original {
+                    piece.original = Some(slice(original, source));
+                }
+                if let Some(trivia) = token.trivia.as_mut() {
+                    if self.first && !trivia.leading.is_empty() {
+                        piece.trivia_mut().leading = mem::take(&mut trivia.leading);
+                    }
+                    if self.pieces.is_empty() && !trivia.trailing.is_empty() {
+                        piece.trivia_mut().trailing = mem::take(&mut trivia.trailing);
+                    }
+                }
+                self.first = false;
+                return Some(self.carried(piece));
+            }
+
+            let mut token = self.iter.next()?;
+            let text = pieces(&token.text, &mut self.pred);
+            if token.text.is_empty() || text.len() == 1 && text[0] == (0..token.text.len()) {
+                return Some(self.carried(token));
+            }
+            if text.is_empty() {
+                if let Some(trivia) = token.trivia.take() {
+                    let carry = self.carry.get_or_insert_with(Box::default);
+                    carry.leading.extend(trivia.leading);
+                    carry.leading.extend(trivia.trailing);
+                }
+                continue;
+            }
+            let source = match &token.original {
+                Some(original) => match pieces(original, &mut self.pred) {
+                    source if source.len() == text.len() => source,
+                    _ => spread(&text, token.text.len(), original),
+                },
+                None => text.clone(),
+            };
+            self.pieces = text.into_iter().zip(source).collect();
+            self.current = Some(token);
+            self.first = true;
+        }
+    }
+
+    fn carried(&mut self, mut token: Token<'a, TK>) -> Token<'a, TK> {
+        if let Some(mut carry) = self.carry.take() {
+            let trivia = token.trivia_mut();
+            carry.leading.append(&mut trivia.leading);
+            trivia.leading = carry.leading;
+        }
+        token
+    }
+}
+
+fn pieces(text: &str, pred: &mut impl FnMut(char) -> bool) -> Vec<Range<usize>> {
+    let mut out = Vec::new();
+    let mut from = None;
+    for (i, c) in text.char_indices() {
+        match (pred(c), from) {
+            (true, Some(f)) => {
+                out.push(f..i);
+                from = None;
+            }
+            (false, None) => from = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(f) = from {
+        out.push(f..text.len());
+    }
+    out
+}
+
+// Source ranges for text pieces that cannot be matched up with the original: cut
+// the original at proportional points so the pieces still partition the token.
+fn spread(text: &[Range<usize>], len: usize, original: &str) -> Vec<Range<usize>> {
+    let mut cuts = vec![0];
+    for piece in &text[1..] {
+        let mut at = piece.start * original.len() / len;
+        while !original.is_char_boundary(at) {
+            at -= 1;
+        }
+        cuts.push(at.max(*cuts.last().unwrap()));
+    }
+    cuts.push(original.len());
+    cuts.windows(2).map(|w| w[0]..w[1]).collect()
+}
+
+#[inline]
+fn slice<'a>(text: &Cow<'a, str>, range: Range<usize>) -> Cow<'a, str> {
+    match text {
+        Cow::Borrowed(s) => Cow::Borrowed(&s[range]),
+        Cow::Owned(s) => Cow::Owned(s[range].to_string()),
+    }
+}
+
+pub struct DropTrivia<I, TK: Copy + Eq + Hash> {
+    iter: I,
+    comments: HashSet<TokenKind<TK>>,
+}
+
+impl<'a, I, TK> Iterator for DropTrivia<I, TK>
+where
+    I: Iterator<Item = Token<'a, TK>>,
+    TK: Copy + Eq + Hash,
+{
+    type Item = Token<'a, TK>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut token = self.iter.find(|t| {
+            let space = matches!(
+                t.kind.base,
+                BaseKind::Space | BaseKind::Newline | BaseKind::Eof
+            );
+            !space && !self.comments.contains(&t.kind)
+        })?;
+        token.trivia = None;
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::classifier::{Classification, Classifier, DefaultClassifier, NoCustom};
+    use crate::lexer::config::Config;
+    use crate::lexer::detok::{reconstruct, Reconstruct};
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::stream::LexerStream;
+    use crate::lexer::token::{classify_base, Newlines};
+    use crate::lexer::trivia::TriviaMode;
+    use std::io::Cursor;
+
+    fn keep_spaces() -> Config<NoCustom> {
+        let mut cfg = Config::default();
+        cfg.skip_base.clear();
+        cfg
+    }
+
+    #[test]
+    fn test_filter_kinds() {
+        let cfg = Config::default();
+        let cls = DefaultClassifier;
+        let out: Vec<_> = LexerInline::new(&cfg, &cls, "abc 12 ! de")
+            .filter_kinds([TokenKind::WORD])
+            .map(|t| t.text)
+            .collect();
+        assert_eq!(out, ["abc", "de"]);
+    }
+
+    #[test]
+    fn test_map_text_owned_tokens() {
+        let cfg = Config::default();
+        let cls = DefaultClassifier;
+        let out: Vec<_> = LexerStream::new(&cfg, &cls, Cursor::new("ab cd"))
+            .map_text(|t| Cow::Owned(t.to_uppercase()))
+            .map(|t| (t.text.into_owned(), t.start))
+            .collect();
+        assert_eq!(out, [("AB".to_string(), 0), ("CD".to_string(), 3)]);
+    }
+
+    #[test]
+    fn test_merge_adjacent() {
+        let cfg = keep_spaces();
+        let cls = DefaultClassifier;
+        let out: Vec<_> = LexerInline::new(&cfg, &cls, "a1b c")
+            .merge_adjacent(|t| t.kind.base != BaseKind::Space)
+            .map(|t| (t.text, t.start))
+            .collect();
+        assert_eq!(
+            out,
+            [
+                (Cow::Borrowed("a1b"), 0),
+                (Cow::Borrowed(" "), 3),
+                (Cow::Borrowed("c"), 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_adjacent_needs_contiguous_tokens() {
+        let cfg = Config::default();
+        let cls = DefaultClassifier;
+        let out: Vec<_> = LexerInline::new(&cfg, &cls, "a1 b\tc2")
+            .merge_adjacent(|t| t.kind.base != BaseKind::Symbol)
+            .map(|t| (t.text.into_owned(), t.start..t.end))
+            .collect();
+        assert_eq!(
+            out,
+            [
+                ("a1".to_string(), 0..2),
+                ("b".to_string(), 3..4),
+                ("c2".to_string(), 5..7)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_adjacent_round_trips_trivia() {
+        let input = " a1 b2\t;c ";
+        for mode in [TriviaMode::Leading, TriviaMode::Trailing] {
+            let cfg = Config::default().with_trivia(mode);
+            let cls = DefaultClassifier;
+            let tokens: Vec<_> = LexerInline::new(&cfg, &cls, input)
+                .merge_adjacent(|t| t.kind.base != BaseKind::Symbol)
+                .collect();
+            assert_eq!(reconstruct(input, &tokens, Reconstruct::Source), input);
+
+            let mut rebuilt = String::new();
+            for token in &tokens {
+                token
+                    .leading()
+                    .iter()
+                    .for_each(|t| rebuilt.push_str(&t.text));
+                rebuilt.push_str(&token.text);
+                token
+                    .trailing()
+                    .iter()
+                    .for_each(|t| rebuilt.push_str(&t.text));
+            }
+            assert_eq!(rebuilt, input);
+        }
+    }
+
+    #[test]
+    fn test_split_on_keeps_offsets() {
+        let tokens = vec![
+            Token {
+                kind: TokenKind::<NoCustom>::WORD,
+                text: Cow::Borrowed("ab_cd__e"),
+                start: 10,
+                end: 18,
+                original: None,
+                trivia: None,
+            },
+            Token {
+                kind: TokenKind::WORD,
+                text: Cow::Owned("_f".into()),
+                start: 19,
+                end: 21,
+                original: None,
+                trivia: None,
+            },
+        ];
+        let out: Vec<_> = tokens
+            .into_iter()
+            .split_on(|c| c == '_')
+            .map(|t| (t.text, t.start))
+            .collect();
+        assert_eq!(
+            out,
+            [
+                (Cow::Borrowed("ab"), 10),
+                (Cow::Borrowed("cd"), 13),
+                (Cow::Borrowed("e"), 17),
+                (Cow::Owned("f".into()), 20)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_on_maps_folded_text_to_source() {
+        let cfg = Config::default().with_case_folding();
+        let cls = DefaultClassifier;
+        let out: Vec<_> = LexerInline::new(&cfg, &cls, "ẞẞxaẞ")
+            .split_on(|c| c == 'x')
+            .map(|t| (t.text.into_owned(), t.start..t.end, t.original))
+            .collect();
+        assert_eq!(
+            out,
+            [
+                ("ssss".to_string(), 0..6, Some(Cow::Borrowed("ẞẞ"))),
+                ("ass".to_string(), 7..11, Some(Cow::Borrowed("aẞ"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_on_partitions_unmatched_source() {
+        let cfg = Config::default().with_case_folding();
+        let cls = DefaultClassifier;
+        let out: Vec<_> = LexerInline::new(&cfg, &cls, "aẞb")
+            .split_on(|c| c == 's')
+            .map(|t| (t.text.into_owned(), t.start..t.end, t.original))
+            .collect();
+        assert_eq!(
+            out,
+            [
+                ("a".to_string(), 0..1, Some(Cow::Borrowed("a"))),
+                ("b".to_string(), 1..5, Some(Cow::Borrowed("ẞb"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_on_keeps_trivia_of_dropped_tokens() {
+        let input = "a _ b; _";
+        for mode in [TriviaMode::Leading, TriviaMode::Trailing] {
+            let cfg = Config::default().with_trivia(mode);
+            let cls = DefaultClassifier;
+            let tokens: Vec<_> = LexerInline::new(&cfg, &cls, input)
+                .split_on(|c| c == '_')
+                .collect();
+            let mut rebuilt = String::new();
+            for token in &tokens {
+                token
+                    .leading()
+                    .iter()
+                    .for_each(|t| rebuilt.push_str(&t.text));
+                rebuilt.push_str(&token.text);
+                token
+                    .trailing()
+                    .iter()
+                    .for_each(|t| rebuilt.push_str(&t.text));
+            }
+            assert_eq!(rebuilt, "a  b; ");
+        }
+    }
+
+    #[test]
+    fn test_drop_trivia() {
+        let cfg = keep_spaces();
+        let cls = DefaultClassifier;
+        let out: Vec<_> = LexerInline::new(&cfg, &cls, "a \t b")
+            .drop_trivia(&cfg)
+            .map(|t| t.start)
+            .collect();
+        assert_eq!(out, [0, 4]);
+    }
+
+    #[test]
+    fn test_drop_trivia_drops_comments() {
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        enum Kind {
+            Comment,
+        }
+        struct Base;
+        impl Classifier for Base {
+            type Custom = Kind;
+            fn classify(&self, c: char) -> Classification<Kind> {
+                (classify_base(c), None, None)
+            }
+        }
+
+        let comment = TokenKind::new(BaseKind::Symbol, Some(Kind::Comment));
+        let cfg = Config::default()
+            .line_comment("//", comment)
+            .block_comment("/*", "*/", false, comment);
+        let out: Vec<_> = LexerInline::new(&cfg, &Base, "x // note\n/* a */ y")
+            .drop_trivia(&cfg)
+            .map(|t| t.text.into_owned())
+            .collect();
+        assert_eq!(out, ["x", "y"]);
+    }
+
+    #[test]
+    fn test_drop_trivia_clears_attached_trivia() {
+        let mut cfg = keep_spaces()
+            .with_newlines(Newlines::PerRun)
+            .with_trivia(TriviaMode::Leading);
+        cfg.skip_base.extend([BaseKind::Space, BaseKind::Newline]);
+        let cls = DefaultClassifier;
+        let out: Vec<_> = LexerInline::new(&cfg, &cls, " a\n b \n")
+            .drop_trivia(&cfg)
+            .map(|t| (t.text.into_owned(), t.trivia.is_none()))
+            .collect();
+        assert_eq!(out, [("a".to_string(), true), ("b".to_string(), true)]);
+
+        let cfg = keep_spaces().with_newlines(Newlines::PerRun);
+        let out: Vec<_> = LexerInline::new(&cfg, &cls, "a\n\nb")
+            .drop_trivia(&cfg)
+            .map(|t| t.text.into_owned())
+            .collect();
+        assert_eq!(out, ["a", "b"]);
+    }
+}
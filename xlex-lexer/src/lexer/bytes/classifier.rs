@@ -0,0 +1,39 @@
+use crate::lexer::classifier::NoCustom;
+use crate::lexer::token::{classify_base, BaseKind};
+
+use std::{borrow::Cow, hash::Hash};
+
+pub type ByteClassification<TK> = (BaseKind, Option<TK>, Option<Cow<'static, [u8]>>);
+
+pub trait ByteClassifier {
+    type Custom: Copy + Eq + Hash;
+
+    fn classify(&self, b: u8) -> ByteClassification<Self::Custom>;
+}
+
+pub struct DefaultByteClassifier;
+
+impl ByteClassifier for DefaultByteClassifier {
+    type Custom = NoCustom;
+
+    #[inline]
+    fn classify(&self, b: u8) -> ByteClassification<Self::Custom> {
+        (classify_base(b as char), None, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_latin1() {
+        let cls = DefaultByteClassifier;
+        assert_eq!(cls.classify(b'a').0, BaseKind::Word);
+        assert_eq!(cls.classify(0xE9).0, BaseKind::Word);
+        assert_eq!(cls.classify(b'7').0, BaseKind::Number);
+        assert_eq!(cls.classify(0xA0).0, BaseKind::Space);
+        assert_eq!(cls.classify(0x00).0, BaseKind::Symbol);
+        assert_eq!(cls.classify(0xFF).0, BaseKind::Word);
+    }
+}
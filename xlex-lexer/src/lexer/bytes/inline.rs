@@ -0,0 +1,245 @@
+use super::classifier::ByteClassifier;
+use super::scan::{Scanner, State, Step};
+use super::token::ByteToken;
+use crate::lexer::compiled::CompiledConfig;
+use crate::lexer::config::Config;
+use crate::lexer::error::{LexError, LexErrorKind};
+
+use std::{borrow::Cow, hash::Hash};
+
+pub struct LexerBytes<'a, TK, CL>
+where
+    TK: Copy + Eq + Hash,
+    CL: ByteClassifier<Custom = TK>,
+{
+    config: Cow<'a, CompiledConfig<'a, TK>>,
+    classifier: &'a CL,
+
+    input: &'a [u8],
+    state: State,
+    error: Option<LexError>,
+}
+
+impl<'a, TK, CL> LexerBytes<'a, TK, CL>
+where
+    TK: Copy + Eq + Hash,
+    CL: ByteClassifier<Custom = TK>,
+{
+    #[inline]
+    pub fn new(config: &'a Config<TK>, classifier: &'a CL, input: &'a [u8]) -> Self {
+        Self::with_config(Cow::Owned(CompiledConfig::new(config)), classifier, input)
+    }
+
+    #[inline]
+    pub fn from_compiled(
+        config: &'a CompiledConfig<'a, TK>,
+        classifier: &'a CL,
+        input: &'a [u8],
+    ) -> Self {
+        Self::with_config(Cow::Borrowed(config), classifier, input)
+    }
+
+    fn with_config(
+        config: Cow<'a, CompiledConfig<'a, TK>>,
+        classifier: &'a CL,
+        input: &'a [u8],
+    ) -> Self {
+        let error = match config.limits.max_input {
+            Some(max) if input.len() > max => Some(LexError::new(LexErrorKind::InputTooLarge, max)),
+            _ => None,
+        };
+        LexerBytes {
+            config,
+            classifier,
+            input,
+            state: State::default(),
+            error,
+        }
+    }
+
+    #[inline]
+    pub fn error(&self) -> Option<&LexError> {
+        self.error.as_ref()
+    }
+}
+
+impl<'a, TK, CL> Iterator for LexerBytes<'a, TK, CL>
+where
+    TK: Copy + Eq + Hash,
+    CL: ByteClassifier<Custom = TK>,
+{
+    type Item = ByteToken<'a, TK>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+        let scanner = Scanner::new(&self.config, self.classifier);
+        match scanner.step(&mut self.state, self.input, 0, true) {
+            Step::Token(raw) => Some(ByteToken {
+                kind: raw.kind,
+                text: match raw.repl {
+                    Some(repl) => Cow::Owned(repl.into_owned()),
+                    None => Cow::Borrowed(&self.input[raw.start..raw.end]),
+                },
+                start: raw.start,
+                end: raw.end,
+            }),
+            Step::Error(e) => {
+                self.error = Some(e);
+                None
+            }
+            Step::More | Step::Done => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::bytes::classifier::{ByteClassification, DefaultByteClassifier};
+    use crate::lexer::bytes::stream::LexerByteStream;
+    use crate::lexer::classifier::{Classification, Classifier};
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::limits::{Limits, Overflow};
+    use crate::lexer::replace::ReplaceMode;
+    use crate::lexer::token::{classify_base, BaseKind, TokenKind};
+
+    #[test]
+    fn test_latin1_and_binary_bytes() {
+        let cfg = Config::default();
+        let cls = DefaultByteClassifier;
+        let tokens: Vec<_> = LexerBytes::new(&cfg, &cls, b"caf\xE9 12\x00\x01!")
+            .map(|t| (t.kind, t.text, t.start))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                (TokenKind::WORD, Cow::Borrowed(&b"caf\xE9"[..]), 0),
+                (TokenKind::NUMBER, Cow::Borrowed(&b"12"[..]), 5),
+                (TokenKind::SYMBOL, Cow::Borrowed(&b"\x00"[..]), 7),
+                (TokenKind::SYMBOL, Cow::Borrowed(&b"\x01"[..]), 8),
+                (TokenKind::SYMBOL, Cow::Borrowed(&b"!"[..]), 9)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_skip_and_grouping() {
+        let cls = DefaultByteClassifier;
+        let cfg = Config::default()
+            .with_grouped_symbols()
+            .skip_base([BaseKind::Number]);
+        let tokens: Vec<_> = LexerBytes::new(&cfg, &cls, b"ab 12 \xFF\xFE!!")
+            .map(|t| t.latin1())
+            .collect();
+        assert_eq!(tokens, ["ab", "ÿþ", "!!"]);
+    }
+
+    #[test]
+    fn test_custom_classifier_with_replacement() {
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        enum Proto {
+            Stx,
+        }
+        struct ProtoClassifier;
+        impl ByteClassifier for ProtoClassifier {
+            type Custom = Proto;
+            fn classify(&self, b: u8) -> ByteClassification<Proto> {
+                match b {
+                    0x02 => (
+                        BaseKind::Symbol,
+                        Some(Proto::Stx),
+                        Some(Cow::Borrowed(b"<STX>")),
+                    ),
+                    _ => (classify_base(b as char), None, None),
+                }
+            }
+        }
+
+        let cfg = Config::default();
+        let tokens: Vec<_> = LexerBytes::new(&cfg, &ProtoClassifier, b"\x02id")
+            .map(|t| (t.kind.custom, t.text.into_owned(), t.end))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                (Some(Proto::Stx), b"<STX>".to_vec(), 1),
+                (None, b"id".to_vec(), 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_token_len() {
+        let cls = DefaultByteClassifier;
+        let cfg =
+            Config::default().with_limits(Limits::default().max_token_len(2, Overflow::Split));
+        let tokens: Vec<_> = LexerBytes::new(&cfg, &cls, b"abcde")
+            .map(|t| t.latin1())
+            .collect();
+        assert_eq!(tokens, ["ab", "cd", "e"]);
+
+        let cfg =
+            Config::default().with_limits(Limits::default().max_token_len(2, Overflow::Error));
+        let mut lexer = LexerBytes::new(&cfg, &cls, b"abcde");
+        assert!(lexer.next().is_none());
+        assert_eq!(lexer.error().unwrap().kind, LexErrorKind::TokenTooLong);
+    }
+
+    #[test]
+    fn test_replace_options_match_char_lexer() {
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        enum Kind {
+            Tab,
+        }
+        struct Upper;
+        impl Classifier for Upper {
+            type Custom = Kind;
+            fn classify(&self, c: char) -> Classification<Kind> {
+                match c {
+                    's' => (BaseKind::Word, None, Some(Cow::Borrowed("S"))),
+                    '\t' => (
+                        BaseKind::Space,
+                        Some(Kind::Tab),
+                        Some(Cow::Borrowed("<TAB>")),
+                    ),
+                    c => (classify_base(c), None, None),
+                }
+            }
+        }
+        impl ByteClassifier for Upper {
+            type Custom = Kind;
+            fn classify(&self, b: u8) -> ByteClassification<Kind> {
+                let (base, custom, repl) = Classifier::classify(self, b as char);
+                let repl = repl.map(|r| Cow::Owned(r.as_bytes().to_vec()));
+                (base, custom, repl)
+            }
+        }
+
+        let input = "sos \t\t sas\tsss";
+        for mode in [
+            ReplaceMode::PerChar,
+            ReplaceMode::InRun,
+            ReplaceMode::WholeRun,
+        ] {
+            let cfg = Config::default()
+                .with_replace(mode)
+                .skip_custom([Kind::Tab]);
+            for cfg in [cfg.clone(), cfg.with_skip_replaced()] {
+                let bytes: Vec<_> = LexerBytes::new(&cfg, &Upper, input.as_bytes())
+                    .map(|t| (t.latin1(), t.start, t.end))
+                    .collect();
+                let chars: Vec<_> = LexerInline::new(&cfg, &Upper, input)
+                    .map(|t| (t.text.into_owned(), t.start, t.end))
+                    .collect();
+                let reader = std::io::BufReader::with_capacity(1, input.as_bytes());
+                let stream: Vec<_> = LexerByteStream::new(&cfg, &Upper, reader)
+                    .map(|t| (t.latin1(), t.start, t.end))
+                    .collect();
+                assert_eq!(bytes, chars);
+                assert_eq!(stream, chars);
+            }
+        }
+    }
+}
@@ -0,0 +1,10 @@
+mod classifier;
+mod inline;
+mod scan;
+mod stream;
+mod token;
+
+pub use classifier::{ByteClassification, ByteClassifier, DefaultByteClassifier};
+pub use inline::LexerBytes;
+pub use stream::LexerByteStream;
+pub use token::ByteToken;
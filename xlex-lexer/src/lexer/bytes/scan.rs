@@ -0,0 +1,162 @@
+use super::classifier::{ByteClassification, ByteClassifier};
+use crate::lexer::compiled::CompiledConfig;
+use crate::lexer::error::{LexError, LexErrorKind};
+use crate::lexer::grouping::Grouping;
+use crate::lexer::limits::Overflow;
+use crate::lexer::replace::ReplaceMode;
+use crate::lexer::token::{is_line_break, BaseKind, Newlines, TokenKind};
+
+use std::{borrow::Cow, hash::Hash};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub(crate) struct State {
+    pub(crate) pos: usize,
+    pub(crate) tokens: usize,
+    pub(crate) run: Option<usize>,
+}
+
+pub(crate) struct Raw<TK: Copy + Eq + Hash> {
+    pub(crate) kind: TokenKind<TK>,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) repl: Option<Cow<'static, [u8]>>,
+}
+
+pub(crate) enum Step<TK: Copy + Eq + Hash> {
+    Token(Raw<TK>),
+    More,
+    Done,
+    Error(LexError),
+}
+
+pub(crate) struct Scanner<'c, TK, CL>
+where
+    TK: Copy + Eq + Hash,
+    CL: ByteClassifier<Custom = TK>,
+{
+    pub(crate) config: &'c CompiledConfig<'c, TK>,
+    pub(crate) classifier: &'c CL,
+}
+
+impl<'c, TK, CL> Scanner<'c, TK, CL>
+where
+    TK: Copy + Eq + Hash,
+    CL: ByteClassifier<Custom = TK>,
+{
+    #[inline]
+    pub(crate) fn new(config: &'c CompiledConfig<'c, TK>, classifier: &'c CL) -> Self {
+        Self { config, classifier }
+    }
+
+    pub(crate) fn step(&self, state: &mut State, input: &[u8], base: usize, eof: bool) -> Step<TK> {
+        let step = self.advance(state, input, base, eof);
+        if let (Step::Token(raw), Some(max)) = (&step, self.config.limits.max_tokens) {
+            if state.tokens == max {
+                return Step::Error(LexError::new(LexErrorKind::TooManyTokens, raw.start));
+            }
+            state.tokens += 1;
+        }
+        step
+    }
+
+    fn advance(&self, state: &mut State, input: &[u8], base: usize, eof: bool) -> Step<TK> {
+        let max = self.config.limits.max_token_len.unwrap_or(usize::MAX);
+        let mode = self.config.replace;
+        loop {
+            let rest = &input[state.pos - base..];
+            let Some(&b) = rest.first() else {
+                return if eof { Step::Done } else { Step::More };
+            };
+            let start = state.pos;
+
+            let (bk, ck, repl) = self.classify(b);
+            let skip = self.config.skips(bk, ck, repl.is_some());
+            let grouping = match skip {
+                true => Grouping::Always,
+                false => self.config.grouping(bk, ck),
+            };
+            let per_break =
+                bk == BaseKind::Newline && self.config.newlines == Some(Newlines::PerBreak);
+
+            let (len, open, full) = if per_break {
+                match rest {
+                    [b'\r', b'\n', ..] => (2, false, false),
+                    [b'\r'] => (1, true, false),
+                    _ => (1, false, false),
+                }
+            } else if (repl.is_some() && mode == ReplaceMode::PerChar)
+                || grouping == Grouping::Never
+            {
+                (1, false, false)
+            } else {
+                let mut len = state.run.take().unwrap_or(1);
+                let mut full = false;
+                for &next in &rest[len..] {
+                    let (nb, nc, nr) = self.classify(next);
+                    let kind = (nb == bk && nc == ck) || self.config.merges(bk, nb);
+                    let same = kind && grouping.extends(len, b as char, next as char);
+                    let fits = match mode {
+                        ReplaceMode::PerChar => nr.is_none(),
+                        ReplaceMode::InRun => self.config.skips(nb, nc, nr.is_some()) == skip,
+                        ReplaceMode::WholeRun => nr == repl,
+                    };
+                    if !same || !fits {
+                        break;
+                    }
+                    if len == max {
+                        full = true;
+                        break;
+                    }
+                    len += 1;
+                }
+                (len, !full && len == rest.len(), full)
+            };
+            if open && !eof {
+                if len > 1 {
+                    state.run = Some(len);
+                }
+                return Step::More;
+            }
+            if full && !skip && self.config.limits.overflow == Overflow::Error {
+                return Step::Error(LexError::new(LexErrorKind::TokenTooLong, start));
+            }
+
+            state.pos += len;
+            if skip {
+                continue;
+            }
+            let repl = match mode {
+                ReplaceMode::InRun if len > 1 => self.join(&rest[..len]),
+                _ => repl,
+            };
+            return Step::Token(Raw {
+                kind: TokenKind::new(bk, ck),
+                start,
+                end: start + len,
+                repl,
+            });
+        }
+    }
+
+    fn join(&self, run: &[u8]) -> Option<Cow<'static, [u8]>> {
+        let mut text: Option<Vec<u8>> = None;
+        for (i, &b) in run.iter().enumerate() {
+            match (&mut text, self.classifier.classify(b).2) {
+                (Some(t), Some(r)) => t.extend_from_slice(&r),
+                (Some(t), None) => t.push(b),
+                (None, Some(r)) => text = Some([&run[..i], &r[..]].concat()),
+                (None, None) => {}
+            }
+        }
+        text.map(Cow::Owned)
+    }
+
+    #[inline]
+    fn classify(&self, b: u8) -> ByteClassification<TK> {
+        let (bk, ck, repl) = self.classifier.classify(b);
+        if bk == BaseKind::Space && self.config.newlines.is_some() && is_line_break(b as char) {
+            return (BaseKind::Newline, ck, repl);
+        }
+        (bk, ck, repl)
+    }
+}
@@ -0,0 +1,219 @@
+use super::classifier::ByteClassifier;
+use super::scan::{Scanner, State, Step};
+use super::token::ByteToken;
+use crate::lexer::compiled::CompiledConfig;
+use crate::lexer::config::Config;
+use crate::lexer::error::{LexError, LexErrorKind};
+
+use std::io::{self, BufRead};
+use std::{borrow::Cow, hash::Hash};
+
+pub struct LexerByteStream<'a, TK, CL, BR>
+where
+    BR: BufRead,
+    TK: Copy + Eq + Hash,
+    CL: ByteClassifier<Custom = TK>,
+{
+    config: Cow<'a, CompiledConfig<'a, TK>>,
+    classifier: &'a CL,
+
+    reader: BR,
+    buf: Vec<u8>,
+    base: usize,
+    eof: bool,
+
+    state: State,
+    error: Option<LexError>,
+}
+
+impl<'a, TK, CL, BR> LexerByteStream<'a, TK, CL, BR>
+where
+    BR: BufRead,
+    TK: Copy + Eq + Hash,
+    CL: ByteClassifier<Custom = TK>,
+{
+    #[inline]
+    pub fn new(config: &'a Config<TK>, classifier: &'a CL, reader: BR) -> Self {
+        Self::with_config(Cow::Owned(CompiledConfig::new(config)), classifier, reader)
+    }
+
+    #[inline]
+    pub fn from_compiled(
+        config: &'a CompiledConfig<'a, TK>,
+        classifier: &'a CL,
+        reader: BR,
+    ) -> Self {
+        Self::with_config(Cow::Borrowed(config), classifier, reader)
+    }
+
+    fn with_config(
+        config: Cow<'a, CompiledConfig<'a, TK>>,
+        classifier: &'a CL,
+        reader: BR,
+    ) -> Self {
+        LexerByteStream {
+            config,
+            classifier,
+            reader,
+            buf: Vec::new(),
+            base: 0,
+            eof: false,
+            state: State::default(),
+            error: None,
+        }
+    }
+
+    #[inline]
+    pub fn error(&self) -> Option<&LexError> {
+        self.error.as_ref()
+    }
+
+    fn fill(&mut self) {
+        let used = self.state.pos - self.base;
+        if used > 0 && used * 2 >= self.buf.len() {
+            self.buf.drain(..used);
+            self.base += used;
+        }
+
+        let data = match self.reader.fill_buf() {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => return,
+            Err(e) => {
+                self.error = Some(LexError::new(LexErrorKind::Io(e.kind()), self.state.pos));
+                return;
+            }
+        };
+        if data.is_empty() {
+            self.eof = true;
+            return;
+        }
+        let taken = data.len();
+        self.buf.extend_from_slice(data);
+        self.reader.consume(taken);
+
+        match self.config.limits.max_input {
+            Some(max) if self.base + self.buf.len() > max => {
+                self.error = Some(LexError::new(LexErrorKind::InputTooLarge, max));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'a, TK, CL, BR> Iterator for LexerByteStream<'a, TK, CL, BR>
+where
+    BR: BufRead,
+    TK: Copy + Eq + Hash,
+    CL: ByteClassifier<Custom = TK>,
+{
+    type Item = ByteToken<'static, TK>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.error.is_some() {
+                return None;
+            }
+            match Scanner::new(&self.config, self.classifier).step(
+                &mut self.state,
+                &self.buf,
+                self.base,
+                self.eof,
+            ) {
+                Step::Token(raw) => {
+                    let text = match raw.repl {
+                        Some(repl) => repl.into_owned(),
+                        None => self.buf[raw.start - self.base..raw.end - self.base].to_vec(),
+                    };
+                    return Some(ByteToken {
+                        kind: raw.kind,
+                        text: Cow::Owned(text),
+                        start: raw.start,
+                        end: raw.end,
+                    });
+                }
+                Step::More => self.fill(),
+                Step::Done => return None,
+                Step::Error(e) => self.error = Some(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::bytes::classifier::{ByteClassification, DefaultByteClassifier};
+    use crate::lexer::bytes::inline::LexerBytes;
+    use crate::lexer::classifier::NoCustom;
+    use crate::lexer::token::{BaseKind, Newlines};
+    use std::cell::Cell;
+    use std::io::{BufReader, Cursor};
+
+    #[test]
+    fn test_stream_matches_inline_across_refills() {
+        let input = b"GET /idx\xE9 HTTP/1.0\r\n\x00\x00\xFFdata  42\r\n";
+        let cfg = Config::default()
+            .with_grouped_symbols()
+            .with_newlines(Newlines::PerBreak);
+        let cls = DefaultByteClassifier;
+
+        let inline: Vec<_> = LexerBytes::new(&cfg, &cls, input)
+            .map(|t| (t.kind, t.text.into_owned(), t.start))
+            .collect();
+        let reader = BufReader::with_capacity(3, Cursor::new(&input[..]));
+        let stream: Vec<_> = LexerByteStream::new(&cfg, &cls, reader)
+            .map(|t| (t.kind, t.text.into_owned(), t.start))
+            .collect();
+
+        assert_eq!(inline, stream);
+        let breaks: Vec<_> = stream
+            .iter()
+            .filter(|t| t.0.base == BaseKind::Newline)
+            .map(|t| t.1.as_slice())
+            .collect();
+        assert_eq!(breaks, [b"\r\n", b"\r\n"]);
+    }
+
+    #[test]
+    fn test_stream_keeps_invalid_utf8() {
+        let cfg = Config::default();
+        let cls = DefaultByteClassifier;
+        let tokens: Vec<_> = LexerByteStream::new(&cfg, &cls, Cursor::new(b"ab\xFF\xC3"))
+            .map(|t| t.latin1())
+            .collect();
+        assert_eq!(tokens, ["abÿÃ"]);
+    }
+
+    #[derive(Default)]
+    struct Counting {
+        calls: Cell<usize>,
+    }
+
+    impl ByteClassifier for Counting {
+        type Custom = NoCustom;
+
+        fn classify(&self, b: u8) -> ByteClassification<NoCustom> {
+            self.calls.set(self.calls.get() + 1);
+            DefaultByteClassifier.classify(b)
+        }
+    }
+
+    #[test]
+    fn test_long_run_is_linear() {
+        let len = 1 << 20;
+        let cfg = Config::default();
+        let cls = Counting::default();
+        let input = vec![b'a'; len];
+        let reader = BufReader::with_capacity(4096, Cursor::new(input));
+        let mut lexer = LexerByteStream::new(&cfg, &cls, reader);
+
+        let token = lexer.next().unwrap();
+        assert_eq!((token.start, token.end), (0, len));
+        assert!(lexer.next().is_none() && lexer.error().is_none());
+        assert!(
+            cls.calls.get() <= len + len / 4096 + 1,
+            "{}",
+            cls.calls.get()
+        );
+    }
+}
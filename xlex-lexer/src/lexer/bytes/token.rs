@@ -0,0 +1,24 @@
+use crate::lexer::token::TokenKind;
+
+use std::{borrow::Cow, hash::Hash, ops::Range};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ByteToken<'a, TK: Copy + Eq + Hash> {
+    pub kind: TokenKind<TK>,
+    pub text: Cow<'a, [u8]>,
+
+    pub start: usize,
+    pub end: usize,
+}
+
+impl<'a, TK: Copy + Eq + Hash> ByteToken<'a, TK> {
+    #[inline]
+    pub fn span(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    #[inline]
+    pub fn latin1(&self) -> String {
+        self.text.iter().map(|&b| b as char).collect()
+    }
+}
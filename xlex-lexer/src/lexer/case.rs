@@ -0,0 +1,43 @@
+use std::borrow::Cow;
+
+pub fn fold_case(text: Cow<'_, str>) -> Cow<'_, str> {
+    if text.is_ascii() {
+        if !text.bytes().any(|b| b.is_ascii_uppercase()) {
+            return text;
+        }
+        return Cow::Owned(text.to_ascii_lowercase());
+    }
+
+    let folded = caseless::default_case_fold_str(&text);
+    if folded == text {
+        text
+    } else {
+        Cow::Owned(folded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_ascii() {
+        assert_eq!(fold_case(Cow::Borrowed("SeLeCt")), "select");
+        assert!(matches!(fold_case(Cow::Borrowed("from")), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_fold_full_unicode() {
+        assert_eq!(fold_case(Cow::Borrowed("Straße")), "strasse");
+        assert_eq!(fold_case(Cow::Borrowed("STRASSE")), "strasse");
+        assert_eq!(fold_case(Cow::Borrowed("ΣΊΣΥΦΟΣ")), "σίσυφοσ");
+    }
+
+    #[test]
+    fn test_fold_unchanged_non_ascii_stays_borrowed() {
+        assert!(matches!(
+            fold_case(Cow::Borrowed("привет")),
+            Cow::Borrowed(_)
+        ));
+    }
+}
@@ -0,0 +1,14 @@
+use super::scan::State;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Checkpoint<S = ()> {
+    pub(crate) state: State,
+    pub(crate) snapshot: S,
+}
+
+impl<S> Checkpoint<S> {
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.state.pos
+    }
+}
@@ -1,11 +1,98 @@
+use super::combine::{Chain, MapCustom, Override, WithReplacements};
 use super::token::{classify_base, BaseKind};
 
 use std::{borrow::Cow, hash::Hash};
 
+pub type Classification<TK> = (BaseKind, Option<TK>, Option<Cow<'static, str>>);
+
 pub trait Classifier {
     type Custom: Copy + Eq + Hash;
 
-    fn classify(&self, c: char) -> (BaseKind, Option<Self::Custom>, Option<Cow<'static, str>>);
+    fn classify(&self, c: char) -> Classification<Self::Custom>;
+
+    #[inline]
+    fn override_with<L>(self, layer: L) -> Override<Self, L>
+    where
+        Self: Sized,
+        L: PartialClassifier<Custom = Self::Custom>,
+    {
+        Override { base: self, layer }
+    }
+
+    #[inline]
+    fn map_custom<TK, F>(self, f: F) -> MapCustom<Self, F, TK>
+    where
+        Self: Sized,
+        TK: Copy + Eq + Hash,
+        F: Fn(Self::Custom) -> TK,
+    {
+        MapCustom::new(self, f)
+    }
+
+    #[inline]
+    fn with_replacements<IT>(self, map: IT) -> WithReplacements<Self>
+    where
+        Self: Sized,
+        IT: IntoIterator<Item = (char, &'static str)>,
+    {
+        WithReplacements {
+            inner: self,
+            map: map.into_iter().collect(),
+        }
+    }
+}
+
+pub trait PartialClassifier {
+    type Custom: Copy + Eq + Hash;
+
+    fn try_classify(&self, c: char) -> Option<Classification<Self::Custom>>;
+
+    #[inline]
+    fn chain<N>(self, next: N) -> Chain<Self, N>
+    where
+        Self: Sized,
+        N: PartialClassifier<Custom = Self::Custom>,
+    {
+        Chain { first: self, next }
+    }
+}
+
+pub trait DynClassifier<TK: Copy + Eq + Hash> {
+    fn classify_dyn(&self, c: char) -> Classification<TK>;
+}
+
+impl<C: Classifier> DynClassifier<C::Custom> for C {
+    #[inline]
+    fn classify_dyn(&self, c: char) -> Classification<C::Custom> {
+        self.classify(c)
+    }
+}
+
+impl<'d, TK: Copy + Eq + Hash> Classifier for dyn DynClassifier<TK> + 'd {
+    type Custom = TK;
+
+    #[inline]
+    fn classify(&self, c: char) -> Classification<TK> {
+        self.classify_dyn(c)
+    }
+}
+
+impl<'d, TK: Copy + Eq + Hash> Classifier for dyn DynClassifier<TK> + Send + Sync + 'd {
+    type Custom = TK;
+
+    #[inline]
+    fn classify(&self, c: char) -> Classification<TK> {
+        self.classify_dyn(c)
+    }
+}
+
+impl<C: Classifier> PartialClassifier for C {
+    type Custom = C::Custom;
+
+    #[inline]
+    fn try_classify(&self, c: char) -> Option<Classification<Self::Custom>> {
+        Some(self.classify(c))
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -17,7 +104,7 @@ impl Classifier for DefaultClassifier {
     type Custom = NoCustom;
 
     #[inline]
-    fn classify(&self, c: char) -> (BaseKind, Option<Self::Custom>, Option<Cow<'static, str>>) {
+    fn classify(&self, c: char) -> Classification<Self::Custom> {
         (classify_base(c), None, None)
     }
 }
@@ -27,8 +114,11 @@ mod tests {
     use super::*;
     use crate::lexer::config::Config;
     use crate::lexer::inline::LexerInline;
+    use crate::lexer::stream::LexerStream;
     use crate::lexer::token::{classify_base, BaseKind, Token, TokenKind};
     use std::borrow::Cow;
+    use std::collections::HashMap;
+    use std::io::Cursor;
 
     #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
     enum MyCustom {
@@ -75,26 +165,41 @@ mod tests {
                 text: Cow::Borrowed("A"),
                 kind: TokenKind::new(BaseKind::Word, Some(MyCustom::LetterA)),
                 start: 0,
+                end: 1,
+                original: Some(Cow::Borrowed("a")),
+                trivia: None,
             },
             Token {
                 text: Cow::Owned("<TAB>".into()),
                 kind: TokenKind::new(BaseKind::Space, Some(MyCustom::Tab)),
                 start: 1,
+                end: 2,
+                original: Some(Cow::Borrowed("\t")),
+                trivia: None,
             },
             Token {
                 text: Cow::Owned("SEVEN".into()),
                 kind: TokenKind::new(BaseKind::Number, Some(MyCustom::Seven)),
                 start: 2,
+                end: 3,
+                original: Some(Cow::Borrowed("7")),
+                trivia: None,
             },
             Token {
                 text: Cow::Borrowed("!"),
                 kind: TokenKind::SYMBOL,
                 start: 3,
+                end: 4,
+                original: None,
+                trivia: None,
             },
             Token {
                 text: Cow::Borrowed("x"),
                 kind: TokenKind::WORD,
                 start: 4,
+                end: 5,
+                original: None,
+                trivia: None,
             },
         ];
 
@@ -113,16 +218,25 @@ mod tests {
                 text: Cow::Borrowed("abc"),
                 kind: TokenKind::WORD,
                 start: 0,
+                end: 3,
+                original: None,
+                trivia: None,
             },
             Token {
                 text: Cow::Borrowed("123"),
                 kind: TokenKind::NUMBER,
                 start: 4,
+                end: 7,
+                original: None,
+                trivia: None,
             },
             Token {
                 text: Cow::Borrowed("!"),
                 kind: TokenKind::SYMBOL,
                 start: 7,
+                end: 8,
+                original: None,
+                trivia: None,
             },
         ];
 
@@ -149,11 +263,17 @@ mod tests {
                 text: Cow::Borrowed("abc"),
                 kind: TokenKind::WORD,
                 start: 0,
+                end: 3,
+                original: None,
+                trivia: None,
             },
             Token {
                 text: Cow::Borrowed("!"),
                 kind: TokenKind::SYMBOL,
                 start: 3,
+                end: 4,
+                original: None,
+                trivia: None,
             },
         ];
 
@@ -172,14 +292,48 @@ mod tests {
                 text: Cow::Borrowed("!!"),
                 kind: TokenKind::SYMBOL,
                 start: 0,
+                end: 2,
+                original: None,
+                trivia: None,
             },
             Token {
                 text: Cow::Owned("SEVEN".into()),
                 kind: TokenKind::new(BaseKind::Number, Some(MyCustom::Seven)),
                 start: 2,
+                end: 3,
+                original: Some(Cow::Borrowed("7")),
+                trivia: None,
             },
         ];
 
         assert_eq!(tokens, expected);
     }
+
+    #[test]
+    fn test_dyn_classifier_registry() {
+        let mut registry: HashMap<&str, Box<dyn DynClassifier<NoCustom> + Send + Sync>> =
+            HashMap::new();
+        registry.insert("default", Box::new(DefaultClassifier));
+        registry.insert(
+            "words",
+            Box::new(DefaultClassifier.with_replacements([('_', "-")])),
+        );
+
+        let cfg = Config::default();
+        let mut lexers: Vec<_> = ["default", "words"]
+            .iter()
+            .map(|name| LexerInline::new(&cfg, &*registry[name], "a_b"))
+            .collect();
+        let texts: Vec<Vec<_>> = lexers
+            .iter_mut()
+            .map(|l| l.map(|t| t.text.into_owned()).collect())
+            .collect();
+        assert_eq!(texts, [vec!["a", "_", "b"], vec!["a", "-", "b"]]);
+
+        let cls: &dyn DynClassifier<NoCustom> = &DefaultClassifier;
+        let stream: Vec<_> = LexerStream::new(&cfg, cls, Cursor::new("x 1"))
+            .map(|t| t.kind)
+            .collect();
+        assert_eq!(stream, [TokenKind::WORD, TokenKind::NUMBER]);
+    }
 }
@@ -0,0 +1,199 @@
+use super::classifier::{Classification, Classifier, PartialClassifier};
+
+use std::{borrow::Cow, collections::HashMap, hash::Hash, marker::PhantomData};
+
+pub struct Override<B, L> {
+    pub(crate) base: B,
+    pub(crate) layer: L,
+}
+
+impl<B, L> Classifier for Override<B, L>
+where
+    B: Classifier,
+    L: PartialClassifier<Custom = B::Custom>,
+{
+    type Custom = B::Custom;
+
+    #[inline]
+    fn classify(&self, c: char) -> Classification<Self::Custom> {
+        match self.layer.try_classify(c) {
+            Some(class) => class,
+            None => self.base.classify(c),
+        }
+    }
+}
+
+pub struct Chain<A, B> {
+    pub(crate) first: A,
+    pub(crate) next: B,
+}
+
+impl<A, B> PartialClassifier for Chain<A, B>
+where
+    A: PartialClassifier,
+    B: PartialClassifier<Custom = A::Custom>,
+{
+    type Custom = A::Custom;
+
+    #[inline]
+    fn try_classify(&self, c: char) -> Option<Classification<Self::Custom>> {
+        self.first
+            .try_classify(c)
+            .or_else(|| self.next.try_classify(c))
+    }
+}
+
+pub struct MapCustom<C, F, TK> {
+    inner: C,
+    f: F,
+    _custom: PhantomData<fn() -> TK>,
+}
+
+impl<C, F, TK> MapCustom<C, F, TK> {
+    #[inline]
+    pub(crate) fn new(inner: C, f: F) -> Self {
+        Self {
+            inner,
+            f,
+            _custom: PhantomData,
+        }
+    }
+}
+
+impl<C, F, TK> Classifier for MapCustom<C, F, TK>
+where
+    C: Classifier,
+    F: Fn(C::Custom) -> TK,
+    TK: Copy + Eq + Hash,
+{
+    type Custom = TK;
+
+    #[inline]
+    fn classify(&self, c: char) -> Classification<TK> {
+        let (bk, ck, repl) = self.inner.classify(c);
+        (bk, ck.map(&self.f), repl)
+    }
+}
+
+pub struct WithReplacements<C> {
+    pub(crate) inner: C,
+    pub(crate) map: HashMap<char, &'static str>,
+}
+
+impl<C: Classifier> Classifier for WithReplacements<C> {
+    type Custom = C::Custom;
+
+    #[inline]
+    fn classify(&self, c: char) -> Classification<Self::Custom> {
+        let (bk, ck, repl) = self.inner.classify(c);
+        match self.map.get(&c) {
+            Some(r) => (bk, ck, Some(Cow::Borrowed(r))),
+            None => (bk, ck, repl),
+        }
+    }
+}
+
+pub struct Layer<TK, F> {
+    f: F,
+    _custom: PhantomData<fn() -> TK>,
+}
+
+#[inline]
+pub fn layer<TK, F>(f: F) -> Layer<TK, F>
+where
+    TK: Copy + Eq + Hash,
+    F: Fn(char) -> Option<Classification<TK>>,
+{
+    Layer {
+        f,
+        _custom: PhantomData,
+    }
+}
+
+impl<TK, F> PartialClassifier for Layer<TK, F>
+where
+    TK: Copy + Eq + Hash,
+    F: Fn(char) -> Option<Classification<TK>>,
+{
+    type Custom = TK;
+
+    #[inline]
+    fn try_classify(&self, c: char) -> Option<Classification<TK>> {
+        (self.f)(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::classifier::{DefaultClassifier, NoCustom};
+    use crate::lexer::config::Config;
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::token::{BaseKind, TokenKind};
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    enum Kind {
+        Dollar,
+        Underscore,
+    }
+
+    #[test]
+    fn test_override_with_layer() {
+        let cls = DefaultClassifier.override_with(layer(|c| match c {
+            '_' => Some((BaseKind::Word, None, None)),
+            _ => None,
+        }));
+        let cfg = Config::default();
+        let tokens: Vec<_> = LexerInline::new(&cfg, &cls, "foo_bar!")
+            .map(|t| t.text)
+            .collect();
+        assert_eq!(tokens, ["foo_bar", "!"]);
+    }
+
+    #[test]
+    fn test_chain_falls_back_in_order() {
+        let first = layer(|c| (c == '$').then_some((BaseKind::Symbol, Some(Kind::Dollar), None)));
+        let second = layer(|c| match c {
+            '$' | '_' => Some((BaseKind::Word, Some(Kind::Underscore), None)),
+            _ => None,
+        });
+        let chain = first.chain(second);
+        assert_eq!(chain.try_classify('$').unwrap().1, Some(Kind::Dollar));
+        assert_eq!(chain.try_classify('_').unwrap().1, Some(Kind::Underscore));
+        assert!(chain.try_classify('a').is_none());
+
+        let cls = DefaultClassifier
+            .map_custom(|_: NoCustom| Kind::Dollar)
+            .override_with(chain);
+        assert_eq!(cls.classify('a'), (BaseKind::Word, None, None));
+        assert_eq!(cls.classify('_').1, Some(Kind::Underscore));
+    }
+
+    #[test]
+    fn test_map_custom() {
+        let base = DefaultClassifier.override_with(layer(|c| {
+            (c == '_').then_some((BaseKind::Symbol, Some(NoCustom), None))
+        }));
+        let cls = base.map_custom(|_| Kind::Underscore);
+        let cfg: Config<Kind> = Config::default();
+        let kinds: Vec<_> = LexerInline::new(&cfg, &cls, "a_").map(|t| t.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::WORD,
+                TokenKind::new(BaseKind::Symbol, Some(Kind::Underscore))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_with_replacements() {
+        let cls = DefaultClassifier.with_replacements([('\t', "<TAB>"), ('→', "->")]);
+        assert!(matches!(cls.classify('\t').2, Some(Cow::Borrowed("<TAB>"))));
+        let cfg = Config::default();
+        let tokens: Vec<_> = LexerInline::new(&cfg, &cls, "a\t→b")
+            .map(|t| format!("{}={}", t.text, t.source()))
+            .collect();
+        assert_eq!(tokens, ["a=a", "<TAB>=\t", "->=→", "b=b"]);
+    }
+}
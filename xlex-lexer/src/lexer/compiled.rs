@@ -0,0 +1,309 @@
+use super::config::Config;
+use super::grouping::Grouping;
+use super::rules::{Found, Rule};
+use super::token::BaseKind;
+
+use std::{collections::HashMap, hash::Hash, ops::Deref};
+
+const BASE_KINDS: [BaseKind; 9] = [
+    BaseKind::Number,
+    BaseKind::Symbol,
+    BaseKind::Space,
+    BaseKind::Word,
+    BaseKind::Indent,
+    BaseKind::Dedent,
+    BaseKind::Newline,
+    BaseKind::Eof,
+    BaseKind::Error,
+];
+
+pub(crate) type IndexFn<TK> = fn(&TK) -> usize;
+
+pub trait KindIndex {
+    const COUNT: usize;
+
+    fn index(&self) -> usize;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+struct Rules {
+    skip: bool,
+    grouping: Option<Grouping>,
+}
+
+#[derive(Debug, Clone)]
+enum Table<TK> {
+    Dense(IndexFn<TK>, Vec<Rules>),
+    Sparse(HashMap<TK, Rules>),
+}
+
+impl<TK: Copy + Eq + Hash> Table<TK> {
+    #[inline]
+    fn get(&self, kind: &TK) -> Rules {
+        match self {
+            Table::Dense(index, rules) => rules.get(index(kind)).copied().unwrap_or_default(),
+            Table::Sparse(rules) => rules.get(kind).copied().unwrap_or_default(),
+        }
+    }
+
+    #[inline]
+    fn entry(&mut self, kind: TK) -> &mut Rules {
+        match self {
+            Table::Dense(index, rules) => &mut rules[index(&kind)],
+            Table::Sparse(rules) => rules.entry(kind).or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompiledConfig<'c, TK: Copy + Eq + Hash> {
+    config: &'c Config<TK>,
+    skip: u16,
+    skip_none: bool,
+    grouping: [Grouping; BASE_KINDS.len()],
+    merges: [u16; BASE_KINDS.len()],
+    custom: Table<TK>,
+    starts: u128,
+    wide_starts: Vec<char>,
+}
+
+impl<'c, TK: Copy + Eq + Hash> CompiledConfig<'c, TK> {
+    pub fn new(config: &'c Config<TK>) -> Self {
+        let mut skip = 0;
+        let mut grouping = [Grouping::Always; BASE_KINDS.len()];
+        let mut merges = [0; BASE_KINDS.len()];
+        for base in BASE_KINDS {
+            if config.skip_base.contains(&base) {
+                skip |= bit(base);
+            }
+            grouping[base as usize] = config.grouping(base, None);
+        }
+        for &(into, from) in &config.merges {
+            merges[into as usize] |= bit(from);
+        }
+
+        let mut custom = match config.kind_index {
+            Some((count, index)) => Table::Dense(index, vec![Rules::default(); count]),
+            None => Table::Sparse(HashMap::new()),
+        };
+        for kind in config.skip_custom.iter().flatten() {
+            custom.entry(*kind).skip = true;
+        }
+        for (kind, policy) in &config.grouping_custom {
+            custom.entry(*kind).grouping = Some(*policy);
+        }
+
+        let mut starts = 0;
+        let mut wide_starts = Vec::new();
+        for rule in &config.rules {
+            let Some(c) = rule.start().chars().next() else {
+                continue;
+            };
+            let cased = matches!(rule, Rule::Literal { .. }) && config.keywords_ignore_case;
+            let upper = cased.then(|| c.to_uppercase()).into_iter().flatten();
+            let lower = cased.then(|| c.to_lowercase()).into_iter().flatten();
+            for c in std::iter::once(c).chain(upper).chain(lower) {
+                match c.is_ascii() {
+                    true => starts |= 1 << c as u32,
+                    false => wide_starts.push(c),
+                }
+            }
+        }
+        wide_starts.sort_unstable();
+        wide_starts.dedup();
+
+        Self {
+            config,
+            skip,
+            skip_none: config.skip_custom.contains(&None),
+            grouping,
+            merges,
+            custom,
+            starts,
+            wide_starts,
+        }
+    }
+
+    #[inline]
+    pub fn should_skip(&self, base: BaseKind, custom: Option<TK>) -> bool {
+        if self.skip & bit(base) != 0 {
+            return true;
+        }
+        match custom {
+            Some(kind) => self.custom.get(&kind).skip,
+            None => self.skip_none,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn skips(&self, base: BaseKind, custom: Option<TK>, replaced: bool) -> bool {
+        (!replaced || self.config.skip_replaced) && self.should_skip(base, custom)
+    }
+
+    #[inline]
+    pub fn grouping(&self, base: BaseKind, custom: Option<TK>) -> Grouping {
+        custom
+            .and_then(|kind| self.custom.get(&kind).grouping)
+            .unwrap_or(self.grouping[base as usize])
+    }
+
+    #[inline]
+    pub fn merges(&self, into: BaseKind, from: BaseKind) -> bool {
+        self.merges[into as usize] & bit(from) != 0
+    }
+
+    pub(crate) fn rule(
+        &self,
+        rest: &str,
+        ch: char,
+        prev: Option<char>,
+        more: bool,
+    ) -> Found<'c, TK> {
+        let starts = match ch.is_ascii() {
+            true => self.starts & (1 << ch as u32) != 0,
+            false => self.wide_starts.binary_search(&ch).is_ok(),
+        };
+        if !starts {
+            return Found::None;
+        }
+        let mut found: Option<&'c Rule<TK>> = None;
+        for rule in &self.config.rules {
+            let start = rule.start();
+            let fold = self.config.keywords_ignore_case && matches!(rule, Rule::Literal { .. });
+            if more && start.len() > rest.len() && prefixed(start, rest, fold) {
+                return Found::More;
+            }
+            if !prefixed(rest, start, fold) || found.is_some_and(|f| start.len() <= f.start().len())
+            {
+                continue;
+            }
+            match rule.bounded(prev, &rest[start.len()..], more) {
+                Some(false) => {}
+                None if more => return Found::More,
+                _ => found = Some(rule),
+            }
+        }
+        match found {
+            Some(rule) => Found::Rule(rule),
+            None => Found::None,
+        }
+    }
+}
+
+impl<'c, TK: Copy + Eq + Hash> Deref for CompiledConfig<'c, TK> {
+    type Target = Config<TK>;
+
+    #[inline]
+    fn deref(&self) -> &Config<TK> {
+        self.config
+    }
+}
+
+fn prefixed(text: &str, prefix: &str, fold: bool) -> bool {
+    if !fold {
+        return text.starts_with(prefix);
+    }
+    text.is_char_boundary(prefix.len().min(text.len()))
+        && text.len() >= prefix.len()
+        && text[..prefix.len()]
+            .chars()
+            .zip(prefix.chars())
+            .all(|(a, b)| {
+                a == b || (a.len_utf8() == b.len_utf8() && a.to_lowercase().eq(b.to_lowercase()))
+            })
+}
+
+#[inline]
+fn bit(base: BaseKind) -> u16 {
+    1 << base as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::classifier::DefaultClassifier;
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::stream::LexerStream;
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, xlex_derive::KindIndex)]
+    enum Kind {
+        Tab,
+        Op,
+        Comment,
+    }
+
+    fn config() -> Config<Kind> {
+        Config::default()
+            .skip_base([BaseKind::Error])
+            .skip_custom([Kind::Comment])
+            .group_custom(Kind::Op, Grouping::Identical)
+            .group(BaseKind::Number, Grouping::UpTo(4))
+            .merge(BaseKind::Word, BaseKind::Number)
+    }
+
+    fn assert_matches(cfg: &Config<Kind>) {
+        let compiled = CompiledConfig::new(cfg);
+        for base in BASE_KINDS {
+            for custom in [None, Some(Kind::Tab), Some(Kind::Op), Some(Kind::Comment)] {
+                assert_eq!(
+                    compiled.should_skip(base, custom),
+                    cfg.should_skip(base, custom)
+                );
+                assert_eq!(compiled.grouping(base, custom), cfg.grouping(base, custom));
+            }
+            for from in BASE_KINDS {
+                assert_eq!(compiled.merges(base, from), cfg.merges(base, from));
+            }
+        }
+    }
+
+    #[test]
+    fn test_derived_index() {
+        assert_eq!(Kind::COUNT, 3);
+        assert_eq!(Kind::Tab.index(), 0);
+        assert_eq!(Kind::Comment.index(), 2);
+    }
+
+    #[test]
+    fn test_sparse_matches_config() {
+        let cfg = config();
+        assert!(matches!(CompiledConfig::new(&cfg).custom, Table::Sparse(_)));
+        assert_matches(&cfg);
+    }
+
+    #[test]
+    fn test_dense_matches_config() {
+        let cfg = config().with_kind_index();
+        assert!(matches!(CompiledConfig::new(&cfg).custom, Table::Dense(..)));
+        assert_matches(&cfg);
+    }
+
+    #[test]
+    fn test_skip_none_custom() {
+        let mut cfg = config();
+        cfg.skip_custom.insert(None);
+        let compiled = CompiledConfig::new(&cfg);
+        assert!(compiled.should_skip(BaseKind::Word, None));
+        assert!(!compiled.should_skip(BaseKind::Word, Some(Kind::Tab)));
+    }
+
+    #[test]
+    fn test_compile_once_lex_many() {
+        let cfg = Config::default().group(BaseKind::Number, Grouping::UpTo(2));
+        let compiled = CompiledConfig::new(&cfg);
+        for input in ["a 1234 b", "99 x"] {
+            let expect: Vec<_> = LexerInline::new(&cfg, &DefaultClassifier, input)
+                .map(|t| t.text.into_owned())
+                .collect();
+            let inline: Vec<_> = LexerInline::from_compiled(&compiled, &DefaultClassifier, input)
+                .map(|t| t.text.into_owned())
+                .collect();
+            let stream: Vec<_> =
+                LexerStream::from_compiled(&compiled, &DefaultClassifier, input.as_bytes())
+                    .map(|t| t.text.into_owned())
+                    .collect();
+            assert_eq!(inline, expect);
+            assert_eq!(stream, expect);
+        }
+    }
+}
@@ -1,6 +1,22 @@
-use super::token::BaseKind;
+use super::case::fold_case;
+use super::compiled::{IndexFn, KindIndex};
+use super::context::ContextClassifier;
+use super::grouping::Grouping;
+use super::invalid::InvalidChar;
+use super::limits::Limits;
+use super::normalize::Normalization;
+use super::offside::Offside;
+use super::replace::ReplaceMode;
+use super::rules::Rule;
+use super::token::{BaseKind, Newlines, Token, TokenKind};
+use super::trivia::TriviaMode;
+use super::validate::Report;
 
-use std::{collections::HashSet, hash::Hash};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Config<TK: Copy + Eq + Hash> {
@@ -8,6 +24,30 @@ pub struct Config<TK: Copy + Eq + Hash> {
     pub skip_custom: HashSet<Option<TK>>,
 
     pub group_symbols: bool,
+    pub grouping: HashMap<BaseKind, Grouping>,
+    pub grouping_custom: HashMap<TK, Grouping>,
+    pub merges: HashSet<(BaseKind, BaseKind)>,
+    pub kind_index: Option<(usize, IndexFn<TK>)>,
+    pub newlines: Option<Newlines>,
+
+    pub replace: ReplaceMode,
+    /// Replaced text is exempt from `skip_base`/`skip_custom` unless this is set.
+    pub skip_replaced: bool,
+
+    pub normalization: Option<Normalization>,
+    pub case_fold: bool,
+
+    pub rules: Vec<Rule<TK>>,
+    pub keywords: HashMap<String, TK>,
+    pub keywords_ignore_case: bool,
+
+    pub offside: Option<Offside>,
+    pub trivia: Option<TriviaMode>,
+
+    pub invalid: HashSet<InvalidChar>,
+    pub strict: bool,
+
+    pub limits: Limits,
 }
 
 impl<TK: Copy + Eq + Hash> Default for Config<TK> {
@@ -20,6 +60,23 @@ impl<TK: Copy + Eq + Hash> Default for Config<TK> {
             skip_base,
             skip_custom: HashSet::new(),
             group_symbols: false,
+            grouping: HashMap::new(),
+            grouping_custom: HashMap::new(),
+            merges: HashSet::new(),
+            kind_index: None,
+            newlines: None,
+            replace: ReplaceMode::PerChar,
+            skip_replaced: false,
+            normalization: None,
+            case_fold: false,
+            rules: Vec::new(),
+            keywords: HashMap::new(),
+            keywords_ignore_case: false,
+            offside: None,
+            trivia: None,
+            invalid: HashSet::new(),
+            strict: false,
+            limits: Limits::default(),
         }
     }
 }
@@ -48,17 +105,285 @@ impl<TK: Copy + Eq + Hash> Config<TK> {
         self.skip_base.contains(&base) || self.skip_custom.contains(&custom)
     }
 
+    #[inline]
+    pub fn reject<IT>(mut self, chars: IT) -> Self
+    where
+        IT: IntoIterator<Item = InvalidChar>,
+    {
+        self.invalid.extend(chars);
+        self
+    }
+
+    #[inline]
+    pub fn with_strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    #[inline]
+    pub fn is_invalid(&self, c: char) -> bool {
+        !self.invalid.is_empty() && self.invalid.iter().any(|i| i.matches(c))
+    }
+
     #[inline]
     pub fn with_grouped_symbols(mut self) -> Self {
         self.group_symbols = true;
         self
     }
+
+    #[inline]
+    pub fn group(mut self, base: BaseKind, policy: Grouping) -> Self {
+        self.grouping.insert(base, policy);
+        self
+    }
+
+    #[inline]
+    pub fn group_custom(mut self, kind: TK, policy: Grouping) -> Self {
+        self.grouping_custom.insert(kind, policy);
+        self
+    }
+
+    #[inline]
+    pub fn merge(mut self, into: BaseKind, from: BaseKind) -> Self {
+        self.merges.insert((into, from));
+        self
+    }
+
+    #[inline]
+    pub fn grouping(&self, base: BaseKind, custom: Option<TK>) -> Grouping {
+        if let Some(policy) = custom.and_then(|c| self.grouping_custom.get(&c)) {
+            return *policy;
+        }
+        match self.grouping.get(&base) {
+            Some(policy) => *policy,
+            None if base == BaseKind::Symbol && !self.group_symbols => Grouping::Never,
+            None => Grouping::Always,
+        }
+    }
+
+    #[inline]
+    pub fn merges(&self, into: BaseKind, from: BaseKind) -> bool {
+        !self.merges.is_empty() && self.merges.contains(&(into, from))
+    }
+
+    #[inline]
+    pub fn with_kind_index(mut self) -> Self
+    where
+        TK: KindIndex,
+    {
+        self.kind_index = Some((TK::COUNT, TK::index));
+        self
+    }
+
+    #[inline]
+    pub fn with_replace(mut self, mode: ReplaceMode) -> Self {
+        self.replace = mode;
+        self
+    }
+
+    #[inline]
+    pub fn with_skip_replaced(mut self) -> Self {
+        self.skip_replaced = true;
+        self
+    }
+
+    #[inline]
+    pub fn with_newlines(mut self, mode: Newlines) -> Self {
+        self.newlines = Some(mode);
+        self
+    }
+
+    #[inline]
+    pub fn with_normalization(mut self, form: Normalization) -> Self {
+        self.normalization = Some(form);
+        self
+    }
+
+    #[inline]
+    pub fn with_offside(mut self, rules: Offside) -> Self {
+        self.offside = Some(rules);
+        self
+    }
+
+    #[inline]
+    pub fn with_trivia(mut self, mode: TriviaMode) -> Self {
+        self.trivia = Some(mode);
+        self
+    }
+
+    #[inline]
+    pub fn with_case_folding(mut self) -> Self {
+        self.case_fold = true;
+        self
+    }
+
+    #[inline]
+    pub fn rule(mut self, rule: Rule<TK>) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    #[inline]
+    pub fn literal(self, text: &str, kind: TokenKind<TK>) -> Self {
+        self.rule(Rule::Literal {
+            text: text.to_string(),
+            kind,
+        })
+    }
+
+    #[inline]
+    pub fn line_comment(self, start: &str, kind: TokenKind<TK>) -> Self {
+        self.rule(Rule::LineComment {
+            start: start.to_string(),
+            standalone: false,
+            kind,
+        })
+    }
+
+    #[inline]
+    pub fn standalone_comment(self, start: &str, kind: TokenKind<TK>) -> Self {
+        self.rule(Rule::LineComment {
+            start: start.to_string(),
+            standalone: true,
+            kind,
+        })
+    }
+
+    #[inline]
+    pub fn block_comment(self, start: &str, end: &str, nested: bool, kind: TokenKind<TK>) -> Self {
+        self.rule(Rule::BlockComment {
+            start: start.to_string(),
+            end: end.to_string(),
+            nested,
+            kind,
+        })
+    }
+
+    #[inline]
+    pub fn quoted(
+        self,
+        open: &str,
+        close: &str,
+        escape: Option<char>,
+        multiline: bool,
+        kind: TokenKind<TK>,
+    ) -> Self {
+        self.rule(Rule::Quoted {
+            open: open.to_string(),
+            close: close.to_string(),
+            escape,
+            multiline,
+            kind,
+        })
+    }
+
+    #[inline]
+    pub fn char_literal(
+        self,
+        open: &str,
+        close: char,
+        escape: Option<char>,
+        kind: TokenKind<TK>,
+    ) -> Self {
+        self.rule(Rule::Char {
+            open: open.to_string(),
+            close,
+            escape,
+            kind,
+        })
+    }
+
+    #[inline]
+    pub fn raw_quoted(self, open: &str, fence: char, quote: char, kind: TokenKind<TK>) -> Self {
+        self.rule(Rule::Raw {
+            open: open.to_string(),
+            fence,
+            quote,
+            kind,
+        })
+    }
+
+    pub fn keywords<'k, IT>(mut self, keywords: IT) -> Self
+    where
+        IT: IntoIterator<Item = (&'k str, TK)>,
+    {
+        for (text, kind) in keywords {
+            let key = match self.keywords_ignore_case {
+                true => fold_case(Cow::Borrowed(text)).into_owned(),
+                false => text.to_string(),
+            };
+            self.keywords.insert(key, kind);
+        }
+        self
+    }
+
+    pub fn with_case_insensitive_keywords(mut self) -> Self {
+        if !self.keywords_ignore_case {
+            self.keywords = self
+                .keywords
+                .into_iter()
+                .map(|(text, kind)| (fold_case(Cow::Owned(text)).into_owned(), kind))
+                .collect();
+            self.keywords_ignore_case = true;
+        }
+        self
+    }
+
+    #[inline]
+    pub fn keyword(&self, text: &str) -> Option<TK> {
+        if self.keywords.is_empty() {
+            return None;
+        }
+        match self.keywords_ignore_case {
+            true => self.keywords.get(fold_case(Cow::Borrowed(text)).as_ref()),
+            false => self.keywords.get(text),
+        }
+        .copied()
+    }
+
+    #[inline]
+    pub fn validate<C>(&self, classifier: &C) -> Report<TK>
+    where
+        C: ContextClassifier<Custom = TK> + ?Sized,
+    {
+        Report::new(self, classifier)
+    }
+
+    #[inline]
+    pub(crate) fn finish<'a>(&self, mut token: Token<'a, TK>) -> Token<'a, TK> {
+        let transforms = self.normalization.is_some() || self.case_fold;
+        let source = match (&token.original, transforms) {
+            (None, true) => Some(token.text.clone()),
+            _ => None,
+        };
+        if let Some(form) = self.normalization {
+            token.text = form.apply(token.text);
+        }
+        let word = token.kind.base == BaseKind::Word && token.original.is_none();
+        if word && token.kind.custom.is_none() {
+            token.kind.custom = self.keyword(&token.text);
+        }
+        if self.case_fold {
+            token.text = fold_case(token.text);
+        }
+        match source {
+            Some(source) if source != token.text => token.original = Some(source),
+            _ => {}
+        }
+        token
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::lexer::token::BaseKind;
+    use crate::lexer::token::{BaseKind, TokenKind};
 
     #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
     enum MyCustom {
@@ -94,4 +419,44 @@ mod tests {
         cfg = cfg.with_grouped_symbols();
         assert!(cfg.group_symbols);
     }
+
+    #[test]
+    fn test_normalization_is_opt_in() {
+        let token = |text| Token {
+            kind: TokenKind::WORD,
+            text: Cow::Borrowed(text),
+            start: 0,
+            end: text.len(),
+            original: None,
+            trivia: None,
+        };
+
+        let cfg: Config<MyCustom> = Config::default();
+        assert_eq!(cfg.finish(token("e\u{301}")).text, "e\u{301}");
+
+        let cfg = cfg.with_normalization(Normalization::Nfc);
+        assert_eq!(cfg.finish(token("e\u{301}")).text, "é");
+    }
+
+    #[test]
+    fn test_keywords_case_sensitive_by_default() {
+        let cfg = Config::default().keywords([("select", MyCustom::Tab)]);
+        assert_eq!(cfg.keyword("select"), Some(MyCustom::Tab));
+        assert_eq!(cfg.keyword("SELECT"), None);
+    }
+
+    #[test]
+    fn test_keywords_ignore_case_any_builder_order() {
+        let before = Config::default()
+            .with_case_insensitive_keywords()
+            .keywords([("Straße", MyCustom::Tab)]);
+        let after = Config::default()
+            .keywords([("Straße", MyCustom::Tab)])
+            .with_case_insensitive_keywords();
+
+        for cfg in [before, after] {
+            assert_eq!(cfg.keyword("STRASSE"), Some(MyCustom::Tab));
+            assert_eq!(cfg.keyword("strasse"), Some(MyCustom::Tab));
+        }
+    }
 }
@@ -0,0 +1,136 @@
+use super::classifier::{Classification, Classifier};
+
+use std::hash::Hash;
+
+pub trait ContextClassifier {
+    type Custom: Copy + Eq + Hash;
+    type State;
+    type Snapshot: Clone;
+
+    #[inline]
+    fn lookahead(&self) -> usize {
+        0
+    }
+
+    fn start(&self) -> Self::State;
+
+    fn classify_at(
+        &self,
+        state: &mut Self::State,
+        prev: Option<char>,
+        c: char,
+        next: &str,
+    ) -> Classification<Self::Custom>;
+
+    fn snapshot(&self, state: &Self::State) -> Self::Snapshot;
+
+    fn restore(&self, state: &mut Self::State, snapshot: Self::Snapshot);
+}
+
+impl<C: Classifier + ?Sized> ContextClassifier for C {
+    type Custom = C::Custom;
+    type State = ();
+    type Snapshot = ();
+
+    #[inline]
+    fn start(&self) {}
+
+    #[inline]
+    fn classify_at(
+        &self,
+        _state: &mut (),
+        _prev: Option<char>,
+        c: char,
+        _next: &str,
+    ) -> Classification<C::Custom> {
+        self.classify(c)
+    }
+
+    #[inline]
+    fn snapshot(&self, _state: &()) {}
+
+    #[inline]
+    fn restore(&self, _state: &mut (), _snapshot: ()) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::config::Config;
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::stream::LexerStream;
+    use crate::lexer::token::{classify_base, BaseKind};
+    use std::io::{BufReader, Cursor};
+
+    struct Prose;
+    impl ContextClassifier for Prose {
+        type Custom = ();
+        type State = ();
+        type Snapshot = ();
+
+        fn lookahead(&self) -> usize {
+            1
+        }
+
+        fn start(&self) {}
+
+        fn classify_at(
+            &self,
+            _state: &mut (),
+            prev: Option<char>,
+            c: char,
+            next: &str,
+        ) -> Classification<()> {
+            let next = next.chars().next();
+            let alpha = |c: Option<char>| c.is_some_and(char::is_alphabetic);
+            let digit = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit());
+            match c {
+                '\'' if alpha(prev) && alpha(next) => (BaseKind::Word, None, None),
+                '.' if digit(prev) && digit(next) => (BaseKind::Number, None, None),
+                '-' if digit(next) && !alpha(prev) && !digit(prev) => {
+                    (BaseKind::Number, None, None)
+                }
+                c => (classify_base(c), None, None),
+            }
+        }
+
+        fn snapshot(&self, _state: &()) {}
+
+        fn restore(&self, _state: &mut (), _snapshot: ()) {}
+    }
+
+    fn inline(input: &str) -> Vec<String> {
+        let cfg = Config::default();
+        LexerInline::new(&cfg, &Prose, input)
+            .map(|t| t.text.into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_apostrophe_inside_word() {
+        assert_eq!(inline("don't 'x'"), ["don't", "'", "x", "'"]);
+    }
+
+    #[test]
+    fn test_decimal_point_and_sign() {
+        assert_eq!(
+            inline("3.14 -2 a-1 end."),
+            ["3.14", "-2", "a", "-", "1", "end", "."]
+        );
+    }
+
+    #[test]
+    fn test_stream_matches_inline_across_refills() {
+        let input = "don't 3.14 x-1 -7 it's.";
+        let cfg = Config::default();
+        let reader = BufReader::with_capacity(1, Cursor::new(input));
+        let stream: Vec<_> = LexerStream::new(&cfg, &Prose, reader)
+            .map(|t| (t.text.into_owned(), t.start, t.end))
+            .collect();
+        let inline: Vec<_> = LexerInline::new(&cfg, &Prose, input)
+            .map(|t| (t.text.into_owned(), t.start, t.end))
+            .collect();
+        assert_eq!(stream, inline);
+        assert!(stream.iter().any(|t| t.0 == "it's"));
+    }
+}
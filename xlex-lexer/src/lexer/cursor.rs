@@ -0,0 +1,264 @@
+use super::token::{Token, TokenKind};
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    error, fmt,
+    hash::Hash,
+};
+
+pub struct TokenCursor<'a, TK, I>
+where
+    TK: Copy + Eq + Hash,
+    I: Iterator<Item = Token<'a, TK>>,
+{
+    iter: I,
+
+    buffer: VecDeque<Token<'a, TK>>,
+    base: usize,
+    pos: usize,
+    end: usize,
+
+    marks: BTreeMap<usize, usize>,
+}
+
+#[must_use]
+#[derive(Debug, PartialEq, Eq)]
+pub struct Mark {
+    index: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorError<TK: Copy + Eq + Hash> {
+    pub expected: TokenKind<TK>,
+    pub found: Option<TokenKind<TK>>,
+    pub offset: usize,
+}
+
+impl<'a, TK, I> TokenCursor<'a, TK, I>
+where
+    TK: Copy + Eq + Hash,
+    I: Iterator<Item = Token<'a, TK>>,
+{
+    #[inline]
+    pub fn new(iter: I) -> Self {
+        TokenCursor {
+            iter,
+            buffer: VecDeque::new(),
+            base: 0,
+            pos: 0,
+            end: 0,
+            marks: BTreeMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    #[inline]
+    pub fn peek(&mut self, n: usize) -> Option<&Token<'a, TK>> {
+        let index = self.pos + n;
+        if !self.fill(index) {
+            return None;
+        }
+        self.buffer.get(index - self.base)
+    }
+
+    #[inline]
+    pub fn peek_kind(&mut self, n: usize) -> Option<TokenKind<TK>> {
+        self.peek(n).map(|t| t.kind)
+    }
+
+    pub fn mark(&mut self) -> Mark {
+        *self.marks.entry(self.pos).or_insert(0) += 1;
+        Mark { index: self.pos }
+    }
+
+    pub fn reset(&mut self, mark: Mark) {
+        self.pos = mark.index;
+        self.release(mark);
+    }
+
+    pub fn release(&mut self, mark: Mark) {
+        if let Some(count) = self.marks.get_mut(&mark.index) {
+            *count -= 1;
+            if *count == 0 {
+                self.marks.remove(&mark.index);
+            }
+        }
+        self.trim();
+    }
+
+    pub fn accept(&mut self, kind: TokenKind<TK>) -> Option<Token<'a, TK>> {
+        match self.peek_kind(0) {
+            Some(k) if k == kind => self.next(),
+            _ => None,
+        }
+    }
+
+    pub fn expect(&mut self, kind: TokenKind<TK>) -> Result<Token<'a, TK>, CursorError<TK>> {
+        if let Some(token) = self.accept(kind) {
+            return Ok(token);
+        }
+        let (found, offset) = match self.peek(0) {
+            Some(t) => (Some(t.kind), t.start),
+            None => (None, self.end),
+        };
+        Err(CursorError {
+            expected: kind,
+            found,
+            offset,
+        })
+    }
+
+    #[inline]
+    fn fill(&mut self, index: usize) -> bool {
+        while self.base + self.buffer.len() <= index {
+            match self.iter.next() {
+                Some(token) => {
+                    self.end = token.end;
+                    self.buffer.push_back(token);
+                }
+                None => return false,
+            }
+        }
+        true
+    }
+
+    #[inline]
+    fn trim(&mut self) {
+        let keep = match self.marks.keys().next() {
+            Some(&oldest) => oldest.min(self.pos),
+            None => self.pos,
+        };
+        while self.base < keep && self.buffer.pop_front().is_some() {
+            self.base += 1;
+        }
+    }
+}
+
+impl<'a, TK, I> Iterator for TokenCursor<'a, TK, I>
+where
+    TK: Copy + Eq + Hash,
+    I: Iterator<Item = Token<'a, TK>>,
+{
+    type Item = Token<'a, TK>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.fill(self.pos) {
+            return None;
+        }
+        self.pos += 1;
+
+        if self.marks.is_empty() && self.base + 1 == self.pos {
+            self.base += 1;
+            return self.buffer.pop_front();
+        }
+        let token = self.buffer[self.pos - 1 - self.base].clone();
+        self.trim();
+        Some(token)
+    }
+}
+
+impl<TK: Copy + Eq + Hash + fmt::Debug> fmt::Display for CursorError<TK> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.found {
+            Some(found) => write!(
+                f,
+                "expected {:?}, found {:?} at offset {}",
+                self.expected, found, self.offset
+            ),
+            None => write!(
+                f,
+                "expected {:?}, found end of input at offset {}",
+                self.expected, self.offset
+            ),
+        }
+    }
+}
+
+impl<TK: Copy + Eq + Hash + fmt::Debug> error::Error for CursorError<TK> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::classifier::DefaultClassifier;
+    use crate::lexer::config::Config;
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::stream::LexerStream;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_peek_does_not_consume() {
+        let cfg = Config::default();
+        let cls = DefaultClassifier;
+        let mut cur = TokenCursor::new(LexerInline::new(&cfg, &cls, "a 1 !"));
+
+        assert_eq!(cur.peek(2).map(|t| t.text.as_ref()), Some("!"));
+        assert_eq!(cur.peek(0).map(|t| t.text.as_ref()), Some("a"));
+        assert!(cur.peek(3).is_none());
+        assert_eq!(cur.next().map(|t| t.start), Some(0));
+    }
+
+    #[test]
+    fn test_mark_and_reset() {
+        let cfg = Config::default();
+        let cls = DefaultClassifier;
+        let mut cur = TokenCursor::new(LexerInline::new(&cfg, &cls, "x y z"));
+
+        cur.next();
+        let mark = cur.mark();
+        assert_eq!(cur.next().unwrap().text, "y");
+        assert_eq!(cur.next().unwrap().text, "z");
+        cur.reset(mark);
+
+        assert_eq!(cur.position(), 1);
+        let rest: Vec<_> = cur.map(|t| t.text).collect();
+        assert_eq!(rest, ["y", "z"]);
+    }
+
+    #[test]
+    fn test_accept_and_expect() {
+        let cfg = Config::default();
+        let cls = DefaultClassifier;
+        let mut cur = TokenCursor::new(LexerInline::new(&cfg, &cls, "ab 12"));
+
+        assert!(cur.accept(TokenKind::NUMBER).is_none());
+        assert_eq!(cur.expect(TokenKind::WORD).unwrap().text, "ab");
+
+        let err = cur.expect(TokenKind::WORD).unwrap_err();
+        assert_eq!(err.found, Some(TokenKind::NUMBER));
+        assert_eq!(err.offset, 3);
+
+        cur.next();
+        let err = cur.expect(TokenKind::WORD).unwrap_err();
+        assert_eq!(err.found, None);
+        assert_eq!(err.offset, 5);
+        assert_eq!(
+            err.to_string(),
+            "expected TokenKind { custom: None, base: Word }, found end of input at offset 5"
+        );
+    }
+
+    #[test]
+    fn test_stream_retains_only_from_oldest_mark() {
+        let cfg = Config::default();
+        let cls = DefaultClassifier;
+        let reader = Cursor::new("a b c d e");
+        let mut cur = TokenCursor::new(LexerStream::new(&cfg, &cls, reader));
+
+        cur.next();
+        cur.next();
+        assert_eq!(cur.buffer.len(), 0);
+
+        let mark = cur.mark();
+        cur.next();
+        cur.next();
+        assert_eq!(cur.buffer.len(), 2);
+
+        cur.release(mark);
+        assert_eq!(cur.buffer.len(), 0);
+        assert_eq!(cur.next().unwrap().text, "e");
+    }
+}
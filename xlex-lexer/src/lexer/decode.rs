@@ -0,0 +1,453 @@
+use std::io::{self, BufRead, Read};
+
+const REPLACEMENT: char = '\u{FFFD}';
+
+const WINDOWS_1252: [u16; 32] = [
+    0x20AC, 0x0081, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0160, 0x2039,
+    0x0152, 0x008D, 0x017D, 0x008F, 0x0090, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014,
+    0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0x009D, 0x017E, 0x0178,
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+    Latin1,
+    Windows1252,
+}
+
+impl Encoding {
+    pub fn detect(bytes: &[u8]) -> Option<(Encoding, usize)> {
+        match bytes {
+            [0xEF, 0xBB, 0xBF, ..] => Some((Encoding::Utf8, 3)),
+            [0xFF, 0xFE, 0x00, 0x00, ..] => Some((Encoding::Utf32Le, 4)),
+            [0x00, 0x00, 0xFE, 0xFF, ..] => Some((Encoding::Utf32Be, 4)),
+            [0xFF, 0xFE, ..] => Some((Encoding::Utf16Le, 2)),
+            [0xFE, 0xFF, ..] => Some((Encoding::Utf16Be, 2)),
+            _ => None,
+        }
+    }
+
+    fn decode(self, bytes: &[u8], eof: bool) -> Option<(char, usize)> {
+        let unit = match self {
+            Encoding::Utf8 | Encoding::Latin1 | Encoding::Windows1252 => 1,
+            Encoding::Utf16Le | Encoding::Utf16Be => 2,
+            Encoding::Utf32Le | Encoding::Utf32Be => 4,
+        };
+        if bytes.len() < unit {
+            return match (eof, bytes.is_empty()) {
+                (true, false) => Some((REPLACEMENT, bytes.len())),
+                _ => None,
+            };
+        }
+
+        match self {
+            Encoding::Utf8 | Encoding::Latin1 => Some((bytes[0] as char, 1)),
+            Encoding::Windows1252 => {
+                let b = bytes[0];
+                let c = match b {
+                    0x80..=0x9F => char::from_u32(WINDOWS_1252[b as usize - 0x80] as u32)?,
+                    _ => b as char,
+                };
+                Some((c, 1))
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let read = |i: usize| match self {
+                    Encoding::Utf16Le => u16::from_le_bytes([bytes[i], bytes[i + 1]]),
+                    _ => u16::from_be_bytes([bytes[i], bytes[i + 1]]),
+                };
+                let first = read(0);
+                if !(0xD800..0xDC00).contains(&first) {
+                    return Some((char::from_u32(first as u32).unwrap_or(REPLACEMENT), 2));
+                }
+                if bytes.len() < 4 {
+                    return eof.then_some((REPLACEMENT, 2));
+                }
+                let second = read(2);
+                if !(0xDC00..0xE000).contains(&second) {
+                    return Some((REPLACEMENT, 2));
+                }
+                let c = 0x10000 + ((first as u32 - 0xD800) << 10) + (second as u32 - 0xDC00);
+                Some((char::from_u32(c).unwrap_or(REPLACEMENT), 4))
+            }
+            Encoding::Utf32Le | Encoding::Utf32Be => {
+                let raw = [bytes[0], bytes[1], bytes[2], bytes[3]];
+                let c = match self {
+                    Encoding::Utf32Le => u32::from_le_bytes(raw),
+                    _ => u32::from_be_bytes(raw),
+                };
+                Some((char::from_u32(c).unwrap_or(REPLACEMENT), 4))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Segment {
+    decoded: usize,
+    original: usize,
+    decoded_width: usize,
+    original_width: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OffsetMap {
+    segments: Vec<Segment>,
+    released: bool,
+}
+
+impl OffsetMap {
+    pub fn original(&self, decoded: usize) -> usize {
+        let i = self.segments.partition_point(|s| s.decoded <= decoded);
+        if i == 0 {
+            return match self.segments.first() {
+                Some(s) if self.released => s.original,
+                _ => decoded,
+            };
+        }
+        let s = self.segments[i - 1];
+        let steps = (decoded - s.decoded) / s.decoded_width;
+        let within = (decoded - s.decoded) % s.decoded_width;
+        s.original + steps * s.original_width + within.min(s.original_width)
+    }
+
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn release(&mut self, decoded: usize) {
+        let i = self.segments.partition_point(|s| s.decoded <= decoded);
+        if i > 1 {
+            self.segments.drain(..i - 1);
+            self.released = true;
+        }
+    }
+
+    #[inline]
+    fn record(
+        &mut self,
+        decoded: usize,
+        original: usize,
+        decoded_width: usize,
+        original_width: usize,
+    ) {
+        let same = self.segments.last().is_some_and(|last| {
+            last.decoded_width == decoded_width && last.original_width == original_width
+        });
+        if same {
+            return;
+        }
+        self.segments.push(Segment {
+            decoded,
+            original,
+            decoded_width,
+            original_width,
+        });
+    }
+}
+
+pub struct Decoder<R: BufRead> {
+    inner: R,
+    encoding: Option<Encoding>,
+    sniffed: bool,
+
+    carry: Vec<u8>,
+    out: Vec<u8>,
+    out_pos: usize,
+
+    original: usize,
+    decoded: usize,
+    map: OffsetMap,
+    eof: bool,
+}
+
+impl<R: BufRead> Decoder<R> {
+    #[inline]
+    pub fn new(inner: R) -> Self {
+        Self::build(inner, None)
+    }
+
+    #[inline]
+    pub fn with_encoding(inner: R, encoding: Encoding) -> Self {
+        Self::build(inner, Some(encoding))
+    }
+
+    fn build(inner: R, encoding: Option<Encoding>) -> Self {
+        Decoder {
+            inner,
+            encoding,
+            sniffed: false,
+            carry: Vec::new(),
+            out: Vec::new(),
+            out_pos: 0,
+            original: 0,
+            decoded: 0,
+            map: OffsetMap::default(),
+            eof: false,
+        }
+    }
+
+    #[inline]
+    pub fn encoding(&self) -> Option<Encoding> {
+        self.encoding
+    }
+
+    #[inline]
+    pub fn offsets(&self) -> &OffsetMap {
+        &self.map
+    }
+
+    #[inline]
+    pub fn original_offset(&self, decoded: usize) -> usize {
+        self.map.original(decoded)
+    }
+
+    #[inline]
+    pub fn release(&mut self, decoded: usize) {
+        self.map.release(decoded);
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    fn sniff(&mut self) -> io::Result<()> {
+        while self.carry.len() < 4 {
+            let data = self.inner.fill_buf()?;
+            if data.is_empty() {
+                break;
+            }
+            let n = data.len().min(4 - self.carry.len());
+            self.carry.extend_from_slice(&data[..n]);
+            self.inner.consume(n);
+        }
+
+        let detected = Encoding::detect(&self.carry);
+        let bom = match (detected, self.encoding) {
+            (Some((found, len)), None) => {
+                self.encoding = Some(found);
+                len
+            }
+            (Some((found, len)), Some(wanted)) if found == wanted => len,
+            (_, None) => {
+                self.encoding = Some(Encoding::Utf8);
+                0
+            }
+            _ => 0,
+        };
+        self.carry.drain(..bom);
+        self.original = bom;
+        Ok(())
+    }
+
+    fn decode(&mut self) -> io::Result<()> {
+        if !self.sniffed {
+            self.sniff()?;
+            self.sniffed = true;
+        }
+        let encoding = self.encoding.unwrap_or(Encoding::Utf8);
+
+        while self.out.is_empty() {
+            let data = self.inner.fill_buf()?;
+            self.eof = data.is_empty();
+            self.carry.extend_from_slice(data);
+            let taken = data.len();
+            self.inner.consume(taken);
+
+            let mut at = 0;
+            let mut utf8 = [0; 4];
+            while let Some((c, len)) = encoding.decode(&self.carry[at..], self.eof) {
+                let bytes: &[u8] = match encoding {
+                    Encoding::Utf8 => &self.carry[at..at + 1],
+                    _ => c.encode_utf8(&mut utf8).as_bytes(),
+                };
+                self.map
+                    .record(self.decoded, self.original, bytes.len(), len);
+                self.out.extend_from_slice(bytes);
+                self.decoded += bytes.len();
+                self.original += len;
+                at += len;
+            }
+            self.carry.drain(..at);
+            if self.eof {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data = self.fill_buf()?;
+        let n = data.len().min(buf.len());
+        buf[..n].copy_from_slice(&data[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Decoder<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.out_pos == self.out.len() {
+            self.out.clear();
+            self.out_pos = 0;
+            if !self.eof {
+                self.decode()?;
+            }
+        }
+        Ok(&self.out[self.out_pos..])
+    }
+
+    #[inline]
+    fn consume(&mut self, amt: usize) {
+        self.out_pos = (self.out_pos + amt).min(self.out.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::classifier::DefaultClassifier;
+    use crate::lexer::config::Config;
+    use crate::lexer::stream::LexerStream;
+    use std::io::{BufReader, Cursor};
+
+    fn utf16le(s: &str) -> Vec<u8> {
+        let mut out = vec![0xFF, 0xFE];
+        s.encode_utf16().for_each(|u| out.extend(u.to_le_bytes()));
+        out
+    }
+
+    fn lex<R: BufRead>(decoder: Decoder<R>) -> Vec<(String, usize, usize)> {
+        let cfg = Config::default();
+        let cls = DefaultClassifier;
+        let mut lexer = LexerStream::new(&cfg, &cls, decoder);
+        let mut out = Vec::new();
+        while let Some(t) = lexer.next() {
+            let original = lexer.get_ref().original_offset(t.start);
+            out.push((t.text.into_owned(), t.start, original));
+        }
+        out
+    }
+
+    #[test]
+    fn test_detect_bom() {
+        assert_eq!(
+            Encoding::detect(b"\xEF\xBB\xBFa"),
+            Some((Encoding::Utf8, 3))
+        );
+        assert_eq!(
+            Encoding::detect(b"\xFF\xFE\0\0"),
+            Some((Encoding::Utf32Le, 4))
+        );
+        assert_eq!(
+            Encoding::detect(b"\xFF\xFEa\0"),
+            Some((Encoding::Utf16Le, 2))
+        );
+        assert_eq!(Encoding::detect(b"\xFE\xFF"), Some((Encoding::Utf16Be, 2)));
+        assert_eq!(Encoding::detect(b"abc"), None);
+    }
+
+    #[test]
+    fn test_utf16le_offsets_across_refills() {
+        let bytes = utf16le("hi wörld 😀x");
+        let reader = BufReader::with_capacity(3, Cursor::new(bytes));
+        let decoder = Decoder::new(reader);
+        assert_eq!(
+            lex(decoder),
+            [
+                ("hi".into(), 0, 2),
+                ("wörld".into(), 3, 8),
+                ("😀".into(), 10, 20),
+                ("x".into(), 14, 24)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_utf16be_and_utf32() {
+        let mut be = vec![0xFE, 0xFF];
+        "ab c"
+            .encode_utf16()
+            .for_each(|u| be.extend(u.to_be_bytes()));
+        let tokens = lex(Decoder::new(Cursor::new(be)));
+        assert_eq!(tokens[1], ("c".into(), 3, 8));
+
+        let mut le32 = vec![0xFF, 0xFE, 0, 0];
+        "é z"
+            .chars()
+            .for_each(|c| le32.extend((c as u32).to_le_bytes()));
+        let decoder = Decoder::new(Cursor::new(le32));
+        let tokens = lex(decoder);
+        assert_eq!(tokens, [("é".into(), 0, 4), ("z".into(), 3, 12)]);
+    }
+
+    #[test]
+    fn test_explicit_single_byte_encodings() {
+        let decoder = Decoder::with_encoding(Cursor::new(b"\x80 5 caf\xE9"), Encoding::Windows1252);
+        assert_eq!(
+            lex(decoder),
+            [
+                ("€".into(), 0, 0),
+                ("5".into(), 4, 2),
+                ("café".into(), 6, 4)
+            ]
+        );
+
+        let mut decoder = Decoder::with_encoding(Cursor::new(b"\x80\xE9"), Encoding::Latin1);
+        let mut s = String::new();
+        decoder.read_to_string(&mut s).unwrap();
+        assert_eq!(s, "\u{80}é");
+    }
+
+    #[test]
+    fn test_utf8_bom_and_unpaired_surrogate() {
+        let tokens = lex(Decoder::new(Cursor::new(b"\xEF\xBB\xBFab cd")));
+        assert_eq!(tokens, [("ab".into(), 0, 3), ("cd".into(), 3, 6)]);
+
+        let mut decoder = Decoder::new(Cursor::new(b"\xFF\xFE\x00\xD8a\x00"));
+        let mut s = String::new();
+        decoder.read_to_string(&mut s).unwrap();
+        assert_eq!(s, "\u{FFFD}a");
+        assert_eq!(decoder.encoding(), Some(Encoding::Utf16Le));
+    }
+
+    #[test]
+    fn test_release_bounds_offset_map() {
+        let text = "aé ".repeat(2000);
+        let cfg = Config::default();
+        let mut lexer = LexerStream::new(
+            &cfg,
+            &DefaultClassifier,
+            Decoder::new(BufReader::with_capacity(64, Cursor::new(utf16le(&text)))),
+        );
+        let mut most = 0;
+        let mut count = 0;
+        while let Some(t) = lexer.next() {
+            assert_eq!(lexer.get_ref().original_offset(t.start), 2 + 6 * count);
+            lexer.get_mut().release(t.start);
+            most = most.max(lexer.get_ref().offsets().len());
+            count += 1;
+        }
+        assert_eq!(count, 2000);
+        assert!(most < 100, "{most}");
+
+        let mut map = OffsetMap::default();
+        map.record(0, 0, 1, 2);
+        map.record(1, 2, 2, 2);
+        map.record(3, 4, 1, 2);
+        map.release(3);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.original(4), 6);
+        assert_eq!(map.original(0), 4);
+    }
+}
@@ -0,0 +1,205 @@
+use super::token::{BaseKind, Token};
+
+use std::{borrow::Borrow, collections::HashSet, hash::Hash};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Reconstruct {
+    Source,
+    Transformed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spacing {
+    pub separator: String,
+    pub newline: String,
+    pub indent: String,
+
+    pub no_space_before: HashSet<String>,
+    pub no_space_after: HashSet<String>,
+}
+
+impl Default for Spacing {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            separator: " ".into(),
+            newline: "\n".into(),
+            indent: "    ".into(),
+            no_space_before: HashSet::new(),
+            no_space_after: HashSet::new(),
+        }
+    }
+}
+
+impl Spacing {
+    #[inline]
+    pub fn separator(mut self, separator: &str) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    #[inline]
+    pub fn newline(mut self, newline: &str) -> Self {
+        self.newline = newline.into();
+        self
+    }
+
+    #[inline]
+    pub fn indent(mut self, unit: &str) -> Self {
+        self.indent = unit.into();
+        self
+    }
+
+    #[inline]
+    pub fn no_space_before<'s, IT>(mut self, texts: IT) -> Self
+    where
+        IT: IntoIterator<Item = &'s str>,
+    {
+        self.no_space_before
+            .extend(texts.into_iter().map(String::from));
+        self
+    }
+
+    #[inline]
+    pub fn no_space_after<'s, IT>(mut self, texts: IT) -> Self
+    where
+        IT: IntoIterator<Item = &'s str>,
+    {
+        self.no_space_after
+            .extend(texts.into_iter().map(String::from));
+        self
+    }
+}
+
+pub fn reconstruct<'a, TK, IT>(input: &str, tokens: IT, mode: Reconstruct) -> String
+where
+    TK: Copy + Eq + Hash,
+    IT: IntoIterator,
+    IT::Item: Borrow<Token<'a, TK>>,
+{
+    let mut out = String::with_capacity(input.len());
+    let mut pos = 0;
+    for token in tokens {
+        let token = token.borrow();
+        if token.start > pos {
+            out.push_str(&input[pos..token.start]);
+        }
+        if token.end < pos {
+            continue;
+        }
+        match mode {
+            Reconstruct::Source => out.push_str(&input[token.start.max(pos)..token.end]),
+            Reconstruct::Transformed => out.push_str(&token.text),
+        }
+        pos = token.end;
+    }
+    out.push_str(&input[pos..]);
+    out
+}
+
+pub fn render<'a, TK, IT>(tokens: IT, spacing: &Spacing) -> String
+where
+    TK: Copy + Eq + Hash,
+    IT: IntoIterator,
+    IT::Item: Borrow<Token<'a, TK>>,
+{
+    let mut out = String::new();
+    let mut depth = 0usize;
+    let mut line_start = true;
+    let mut glue = true;
+
+    for token in tokens {
+        let token = token.borrow();
+        match token.kind.base {
+            BaseKind::Indent => depth += 1,
+            BaseKind::Dedent => depth = depth.saturating_sub(1),
+            BaseKind::Eof => {}
+            BaseKind::Newline => {
+                out.push_str(&spacing.newline);
+                line_start = true;
+                glue = true;
+            }
+            BaseKind::Space => {
+                out.push_str(&token.text);
+                glue = true;
+            }
+            _ => {
+                if line_start {
+                    (0..depth).for_each(|_| out.push_str(&spacing.indent));
+                } else if !glue && !spacing.no_space_before.contains(token.text.as_ref()) {
+                    out.push_str(&spacing.separator);
+                }
+                out.push_str(&token.text);
+                line_start = false;
+                glue = spacing.no_space_after.contains(token.text.as_ref());
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::classifier::{Classifier, DefaultClassifier};
+    use crate::lexer::config::Config;
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::offside::Offside;
+    use crate::lexer::token::classify_base;
+    use std::borrow::Cow;
+
+    struct Arrows;
+    impl Classifier for Arrows {
+        type Custom = ();
+
+        fn classify(&self, c: char) -> (BaseKind, Option<()>, Option<Cow<'static, str>>) {
+            match c {
+                '→' => (BaseKind::Symbol, None, Some(Cow::Borrowed("->"))),
+                _ => (classify_base(c), None, None),
+            }
+        }
+    }
+
+    const INPUT: &str = "  a → b\t→ c  ";
+
+    #[test]
+    fn test_reconstruct_source() {
+        let cfg = Config::default();
+        let tokens: Vec<_> = LexerInline::new(&cfg, &Arrows, INPUT).collect();
+        assert_eq!(reconstruct(INPUT, &tokens, Reconstruct::Source), INPUT);
+    }
+
+    #[test]
+    fn test_reconstruct_transformed_keeps_gaps() {
+        let cfg = Config::default();
+        let tokens = LexerInline::new(&cfg, &Arrows, INPUT);
+        assert_eq!(
+            reconstruct(INPUT, tokens, Reconstruct::Transformed),
+            "  a -> b\t-> c  "
+        );
+    }
+
+    #[test]
+    fn test_render_with_spacing_rules() {
+        let cfg = Config::default();
+        let cls = DefaultClassifier;
+        let tokens: Vec<_> = LexerInline::new(&cfg, &cls, "f( a ,b )  ;").collect();
+
+        assert_eq!(render(&tokens, &Spacing::default()), "f ( a , b ) ;");
+
+        let spacing = Spacing::default()
+            .no_space_before([",", ")", ";", "("])
+            .no_space_after(["("]);
+        assert_eq!(render(&tokens, &spacing), "f(a, b);");
+    }
+
+    #[test]
+    fn test_render_layout_tokens() {
+        let cfg = Config::default().with_offside(Offside::default());
+        let cls = DefaultClassifier;
+        let tokens: Vec<_> = LexerInline::new(&cfg, &cls, "if x:\n      y\nz").collect();
+
+        let spacing = Spacing::default().no_space_before([":"]).indent("  ");
+        assert_eq!(render(&tokens, &spacing), "if x:\n  y\nz\n");
+    }
+}
@@ -0,0 +1,50 @@
+use std::{error, fmt, io};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexErrorKind {
+    MixedIndentation,
+    InconsistentDedent,
+    IndentTooDeep,
+    InvalidChar(char),
+    TokenTooLong,
+    TooManyTokens,
+    InputTooLarge,
+    Unterminated,
+    Io(io::ErrorKind),
+}
+
+impl LexError {
+    #[inline]
+    pub fn new(kind: LexErrorKind, offset: usize) -> Self {
+        Self { kind, offset }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            LexErrorKind::MixedIndentation => {
+                f.write_str("mixed tabs and spaces in indentation")?
+            }
+            LexErrorKind::InconsistentDedent => {
+                f.write_str("dedent does not match any outer indentation level")?
+            }
+            LexErrorKind::IndentTooDeep => f.write_str("too many indentation levels")?,
+            LexErrorKind::InvalidChar(c) => write!(f, "invalid character U+{:04X}", *c as u32)?,
+            LexErrorKind::TokenTooLong => f.write_str("token exceeds maximum length")?,
+            LexErrorKind::TooManyTokens => f.write_str("token limit exceeded")?,
+            LexErrorKind::InputTooLarge => f.write_str("input exceeds maximum size")?,
+            LexErrorKind::Unterminated => f.write_str("unterminated comment or string")?,
+            LexErrorKind::Io(kind) => write!(f, "read failed: {kind}")?,
+        }
+        write!(f, " at offset {}", self.offset)
+    }
+}
+
+impl error::Error for LexError {}
@@ -0,0 +1,118 @@
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Grouping {
+    Always,
+    Never,
+    UpTo(usize),
+    Identical,
+}
+
+impl Grouping {
+    #[inline]
+    pub(crate) fn extends(self, count: usize, first: char, c: char) -> bool {
+        match self {
+            Grouping::Always => true,
+            Grouping::Never => false,
+            Grouping::UpTo(max) => count < max,
+            Grouping::Identical => c == first,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::bytes::{DefaultByteClassifier, LexerBytes};
+    use crate::lexer::classifier::{Classification, Classifier, DefaultClassifier};
+    use crate::lexer::config::Config;
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::stream::LexerStream;
+    use crate::lexer::token::{classify_base, BaseKind};
+    use std::io::{BufReader, Cursor};
+
+    fn texts<TK: Copy + Eq + std::hash::Hash>(
+        cfg: &Config<TK>,
+        cls: &impl Classifier<Custom = TK>,
+        input: &str,
+    ) -> Vec<String> {
+        LexerInline::new(cfg, cls, input)
+            .map(|t| t.text.into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_defaults_follow_group_symbols() {
+        let cfg = Config::default();
+        assert_eq!(cfg.grouping(BaseKind::Symbol, None), Grouping::Never);
+        assert_eq!(cfg.grouping(BaseKind::Word, None), Grouping::Always);
+        assert_eq!(
+            texts(&cfg, &DefaultClassifier, "ab 12 !!"),
+            ["ab", "12", "!", "!"]
+        );
+
+        let cfg = cfg.with_grouped_symbols();
+        assert_eq!(cfg.grouping(BaseKind::Symbol, None), Grouping::Always);
+    }
+
+    #[test]
+    fn test_policies() {
+        let cfg = Config::default()
+            .group(BaseKind::Word, Grouping::Never)
+            .group(BaseKind::Number, Grouping::UpTo(2))
+            .group(BaseKind::Symbol, Grouping::Identical);
+        assert_eq!(
+            texts(&cfg, &DefaultClassifier, "ab 12345 !!?"),
+            ["a", "b", "12", "34", "5", "!!", "?"]
+        );
+    }
+
+    #[test]
+    fn test_custom_policy_wins_over_base() {
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        struct Op;
+
+        struct Ops;
+        impl Classifier for Ops {
+            type Custom = Op;
+
+            fn classify(&self, c: char) -> Classification<Op> {
+                match c {
+                    '+' | '=' => (BaseKind::Symbol, Some(Op), None),
+                    c => (classify_base(c), None, None),
+                }
+            }
+        }
+
+        let cfg = Config::default()
+            .with_grouped_symbols()
+            .group_custom(Op, Grouping::UpTo(2));
+        assert_eq!(texts(&cfg, &Ops, "+++= ;;"), ["++", "+=", ";;"]);
+    }
+
+    #[test]
+    fn test_word_absorbs_number() {
+        let cfg = Config::default().merge(BaseKind::Word, BaseKind::Number);
+        assert_eq!(
+            texts(&cfg, &DefaultClassifier, "abc123 123abc"),
+            ["abc123", "123", "abc"]
+        );
+
+        let bytes: Vec<_> = LexerBytes::new(&cfg, &DefaultByteClassifier, b"x1 2y")
+            .map(|t| t.text.into_owned())
+            .collect();
+        assert_eq!(bytes, [b"x1".to_vec(), b"2".to_vec(), b"y".to_vec()]);
+    }
+
+    #[test]
+    fn test_stream_matches_inline() {
+        let input = "abc123 !!?? 1234567";
+        let cfg = Config::default()
+            .group(BaseKind::Symbol, Grouping::Identical)
+            .group(BaseKind::Number, Grouping::UpTo(3))
+            .merge(BaseKind::Word, BaseKind::Number);
+        let reader = BufReader::with_capacity(1, Cursor::new(input));
+        let stream: Vec<_> = LexerStream::new(&cfg, &DefaultClassifier, reader)
+            .map(|t| t.text.into_owned())
+            .collect();
+        assert_eq!(stream, texts(&cfg, &DefaultClassifier, input));
+    }
+}
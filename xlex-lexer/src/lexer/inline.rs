@@ -1,81 +1,143 @@
-use super::classifier::Classifier;
+use super::checkpoint::Checkpoint;
+use super::compiled::CompiledConfig;
 use super::config::Config;
-use super::token::{BaseKind, Token, TokenKind};
+use super::context::ContextClassifier;
+use super::error::{LexError, LexErrorKind};
+use super::scan::{Scanner, State, Step, Tail};
+use super::token::Token;
+use super::trivia::{Attach, TriviaMode};
 
-use std::{borrow::Cow, hash::Hash, str::CharIndices};
+use std::{borrow::Cow, hash::Hash};
 
 pub struct LexerInline<'a, TK, CL>
 where
     TK: Copy + Eq + Hash,
-    CL: Classifier<Custom = TK>,
+    CL: ContextClassifier<Custom = TK> + ?Sized,
 {
-    config: &'a Config<TK>,
+    config: Cow<'a, CompiledConfig<'a, TK>>,
     classifier: &'a CL,
+    context: CL::State,
 
     input: &'a str,
-    pos: usize,
+    state: State,
+    attach: Attach<'a, TK, CL::Snapshot>,
+    error: Option<LexError>,
 }
 
 impl<'a, TK, CL> LexerInline<'a, TK, CL>
 where
     TK: Copy + Eq + Hash,
-    CL: Classifier<Custom = TK>,
+    CL: ContextClassifier<Custom = TK> + ?Sized,
 {
     #[inline]
     pub fn new(config: &'a Config<TK>, classifier: &'a CL, input: &'a str) -> Self {
+        Self::with_config(Cow::Owned(CompiledConfig::new(config)), classifier, input)
+    }
+
+    #[inline]
+    pub fn from_compiled(
+        config: &'a CompiledConfig<'a, TK>,
+        classifier: &'a CL,
+        input: &'a str,
+    ) -> Self {
+        Self::with_config(Cow::Borrowed(config), classifier, input)
+    }
+
+    fn with_config(
+        config: Cow<'a, CompiledConfig<'a, TK>>,
+        classifier: &'a CL,
+        input: &'a str,
+    ) -> Self {
+        let error = match config.limits.max_input {
+            Some(max) if input.len() > max => Some(LexError::new(LexErrorKind::InputTooLarge, max)),
+            _ => None,
+        };
         LexerInline {
             config,
             classifier,
+            context: classifier.start(),
             input,
-            pos: 0,
+            state: State::default(),
+            attach: Attach::default(),
+            error,
         }
     }
 
     #[inline]
-    fn next_token(&mut self) -> Option<Token<'a, TK>> {
-        while self.pos < self.input.len() {
-            let start = self.pos;
-
-            let mut i = self.input[self.pos..].char_indices();
-            let (_, ch) = i.next().unwrap();
-            self.pos += ch.len_utf8();
-
-            let (bk, ck, repl) = self.classifier.classify(ch);
-            let kind = TokenKind::new(bk, ck);
-
-            if let Some(repl) = repl {
-                return Some(Token {
-                    text: Cow::Owned(repl.into_owned()),
-                    kind,
-                    start,
-                });
-            }
-            if self.config.should_skip(bk, ck) {
-                self.consume_while(bk, ck, &mut i);
-                continue;
-            }
-            if (bk == BaseKind::Symbol && self.config.group_symbols) || bk != BaseKind::Symbol {
-                self.consume_while(bk, ck, &mut i);
-            }
-
-            return Some(Token {
-                text: Cow::Borrowed(&self.input[start..self.pos]),
-                start,
-                kind,
-            });
+    pub fn checkpoint(&self) -> Checkpoint<CL::Snapshot> {
+        match self.config.trivia {
+            Some(_) => self
+                .attach
+                .checkpoint(|| self.classifier.snapshot(&self.context)),
+            None => Checkpoint {
+                state: self.state,
+                snapshot: self.classifier.snapshot(&self.context),
+            },
         }
+    }
 
-        None
+    #[inline]
+    pub fn restore(&mut self, checkpoint: Checkpoint<CL::Snapshot>) {
+        self.state = checkpoint.state;
+        self.attach.reset(checkpoint.state);
+        self.classifier
+            .restore(&mut self.context, checkpoint.snapshot);
+        self.error = self
+            .error
+            .take()
+            .filter(|e| e.kind == LexErrorKind::InputTooLarge);
+    }
+
+    #[inline]
+    pub fn error(&self) -> Option<&LexError> {
+        self.error.as_ref()
     }
 
     #[inline]
-    fn consume_while(&mut self, base: BaseKind, custom: Option<TK>, i: &mut CharIndices) {
-        while let Some((_, ch)) = i.next() {
-            let (bk, ck, repl) = self.classifier.classify(ch);
-            if repl.is_some() || bk != base || ck != custom {
-                break;
+    fn next_token(&mut self) -> Option<Token<'a, TK>> {
+        let scanner = Scanner::new(&self.config, self.classifier);
+        loop {
+            if self.error.is_some() {
+                return self.attach.flush();
+            }
+            let before = self.state;
+            let saved = match self.config.trivia {
+                Some(TriviaMode::Trailing) => Some(self.classifier.snapshot(&self.context)),
+                _ => None,
+            };
+            match scanner.step(
+                &mut self.state,
+                &mut self.context,
+                &mut None,
+                self.input,
+                0,
+                Tail::Eof,
+            ) {
+                Step::Token(raw) => {
+                    let source = &self.input[raw.start..raw.end];
+                    let mut token = Token::new(raw.kind, source, raw.start, raw.end);
+                    if let Some(repl) = raw.repl {
+                        token.text = Cow::Owned(repl.into_owned());
+                        token.original = Some(Cow::Borrowed(source));
+                    }
+                    let token = self.config.finish(token);
+                    let Some(mode) = self.config.trivia else {
+                        return Some(token);
+                    };
+                    if let Some(token) = self.attach.token(mode, token, before, self.state, saved) {
+                        return Some(token);
+                    }
+                }
+                Step::Skipped(raw) => {
+                    let text = &self.input[raw.start..raw.end];
+                    if let Some(mode) = self.config.trivia {
+                        self.attach
+                            .trivia(mode, Token::new(raw.kind, text, raw.start, raw.end));
+                    }
+                }
+                Step::Error(e) => self.error = Some(e),
+                Step::More | Step::Done => return self.attach.finish(self.state.pos, self.state),
             }
-            self.pos += ch.len_utf8();
         }
     }
 }
@@ -83,7 +145,7 @@ where
 impl<'a, TK, CL> Iterator for LexerInline<'a, TK, CL>
 where
     TK: Copy + Eq + Hash,
-    CL: Classifier<Custom = TK>,
+    CL: ContextClassifier<Custom = TK> + ?Sized,
 {
     type Item = Token<'a, TK>;
 
@@ -96,9 +158,10 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::lexer::classifier::DefaultClassifier;
+    use crate::lexer::classifier::{Classifier, DefaultClassifier};
     use crate::lexer::config::Config;
-    use crate::lexer::token::{classify_base, BaseKind};
+    use crate::lexer::normalize::Normalization;
+    use crate::lexer::token::{classify_base, BaseKind, Newlines, TokenKind};
     use std::borrow::Cow;
 
     #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -233,4 +296,135 @@ mod tests {
             &["abc"]
         );
     }
+
+    #[test]
+    fn test_normalization_joins_combining_marks() {
+        let cfg = Config::default().with_normalization(Normalization::Nfc);
+        let cls = DefaultClassifier;
+        let input = "cafe\u{301} bar";
+        let out: Vec<_> = LexerInline::new(&cfg, &cls, input).collect();
+        assert_eq!(
+            out.iter().map(|t| t.text.as_ref()).collect::<Vec<_>>(),
+            &["café", "bar"]
+        );
+        assert_eq!(out[1].start, 7);
+        assert!(matches!(out[0].text, Cow::Owned(_)));
+        assert!(matches!(out[1].text, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_normalization_nfkc_fullwidth() {
+        let cfg = Config::default().with_normalization(Normalization::Nfkc);
+        let cls = DefaultClassifier;
+        let input = "ＡＢＣ ﬁne";
+        let out: Vec<_> = LexerInline::new(&cfg, &cls, input).collect();
+        assert_eq!(
+            out.iter().map(|t| t.text.as_ref()).collect::<Vec<_>>(),
+            &["ABC", "fine"]
+        );
+        assert_eq!(out[1].start, 10);
+    }
+
+    #[test]
+    fn test_case_insensitive_keywords() {
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        enum Sql {
+            Select,
+            From,
+        }
+
+        struct SqlClassifier;
+        impl Classifier for SqlClassifier {
+            type Custom = Sql;
+
+            fn classify(&self, c: char) -> (BaseKind, Option<Sql>, Option<Cow<'static, str>>) {
+                (classify_base(c), None, None)
+            }
+        }
+
+        let cfg = Config::default()
+            .keywords([("select", Sql::Select), ("from", Sql::From)])
+            .with_case_insensitive_keywords();
+        let cls = SqlClassifier;
+        let out: Vec<_> = LexerInline::new(&cfg, &cls, "SELECT x From t").collect();
+
+        let kinds: Vec<_> = out.iter().map(|t| t.kind).collect();
+        assert_eq!(
+            kinds,
+            &[
+                TokenKind::new(BaseKind::Word, Some(Sql::Select)),
+                TokenKind::WORD,
+                TokenKind::new(BaseKind::Word, Some(Sql::From)),
+                TokenKind::WORD,
+            ]
+        );
+        assert_eq!(out[0].text, "SELECT");
+    }
+
+    #[test]
+    fn test_case_folding_tokens() {
+        let cfg = Config::default().with_case_folding();
+        let cls = DefaultClassifier;
+        let out: Vec<_> = LexerInline::new(&cfg, &cls, "Straße STRASSE abc").collect();
+        assert_eq!(
+            out.iter().map(|t| t.text.as_ref()).collect::<Vec<_>>(),
+            &["strasse", "strasse", "abc"]
+        );
+        assert_eq!(out[1].start, 8);
+        assert!(matches!(out[2].text, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_checkpoint_restore_replays_tokens() {
+        let cfg = Config::default();
+        let cls = MyClassifier;
+        let mut lexer = LexerInline::new(&cfg, &cls, "x a7 yy");
+
+        lexer.next();
+        let cp = lexer.checkpoint();
+        let first: Vec<_> = lexer.by_ref().collect();
+        assert!(lexer.next().is_none());
+
+        lexer.restore(cp);
+        let second: Vec<_> = lexer.collect();
+        assert_eq!(first, second);
+        assert_eq!(cp.offset(), 1);
+        assert_eq!(
+            second.iter().map(|t| t.text.as_ref()).collect::<Vec<_>>(),
+            &["A", "SEVEN", "yy"]
+        );
+    }
+
+    #[test]
+    fn test_newlines_per_break() {
+        let cfg = Config::default().with_newlines(Newlines::PerBreak);
+        let cls = DefaultClassifier;
+        let out: Vec<_> = LexerInline::new(&cfg, &cls, "a \n\n b\r\n\u{2028}c").collect();
+        assert_eq!(
+            out.iter()
+                .map(|t| (t.text.as_ref(), t.start))
+                .collect::<Vec<_>>(),
+            &[
+                ("a", 0),
+                ("\n", 2),
+                ("\n", 3),
+                ("b", 5),
+                ("\r\n", 6),
+                ("\u{2028}", 8),
+                ("c", 11)
+            ]
+        );
+        assert_eq!(out[1].kind, TokenKind::NEWLINE);
+    }
+
+    #[test]
+    fn test_newlines_per_run() {
+        let cfg = Config::default().with_newlines(Newlines::PerRun);
+        let cls = DefaultClassifier;
+        let out: Vec<_> = LexerInline::new(&cfg, &cls, "a \r\n\r\n\t b\n").collect();
+        assert_eq!(
+            out.iter().map(|t| t.text.as_ref()).collect::<Vec<_>>(),
+            &["a", "\r\n\r\n", "b", "\n"]
+        );
+    }
 }
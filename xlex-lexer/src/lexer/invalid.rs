@@ -0,0 +1,110 @@
+use unicode_general_category::{get_general_category, GeneralCategory};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum InvalidChar {
+    Control,
+    PrivateUse,
+    Unassigned,
+}
+
+impl InvalidChar {
+    #[inline]
+    pub fn matches(self, c: char) -> bool {
+        if c.is_ascii() {
+            return self == InvalidChar::Control && c.is_ascii_control() && !c.is_whitespace();
+        }
+        match (self, get_general_category(c)) {
+            (InvalidChar::Control, GeneralCategory::Control) => !c.is_whitespace(),
+            (InvalidChar::PrivateUse, GeneralCategory::PrivateUse) => true,
+            (InvalidChar::Unassigned, GeneralCategory::Unassigned) => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::classifier::{Classifier, DefaultClassifier};
+    use crate::lexer::config::Config;
+    use crate::lexer::error::LexErrorKind;
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::stream::LexerStream;
+    use crate::lexer::token::{classify_base, BaseKind, TokenKind};
+    use std::borrow::Cow;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_matches_categories() {
+        assert!(InvalidChar::Control.matches('\u{0}'));
+        assert!(InvalidChar::Control.matches('\u{9F}'));
+        assert!(!InvalidChar::Control.matches('\t'));
+        assert!(!InvalidChar::Control.matches('\u{85}'));
+        assert!(InvalidChar::PrivateUse.matches('\u{E000}'));
+        assert!(InvalidChar::Unassigned.matches('\u{378}'));
+        assert!(!InvalidChar::Unassigned.matches('a'));
+    }
+
+    #[test]
+    fn test_error_tokens_allow_recovery() {
+        let cfg = Config::default().reject([InvalidChar::Control, InvalidChar::PrivateUse]);
+        let cls = DefaultClassifier;
+        let mut lexer = LexerInline::new(&cfg, &cls, "a\u{1}\u{2}b \u{E000}!");
+        let tokens: Vec<_> = lexer.by_ref().map(|t| (t.kind, t.start)).collect();
+
+        assert_eq!(
+            tokens,
+            [
+                (TokenKind::WORD, 0),
+                (TokenKind::ERROR, 1),
+                (TokenKind::WORD, 3),
+                (TokenKind::ERROR, 5),
+                (TokenKind::SYMBOL, 8)
+            ]
+        );
+        assert!(lexer.error().is_none());
+    }
+
+    #[test]
+    fn test_strict_mode_stops_with_position() {
+        let cfg = Config::default()
+            .reject([InvalidChar::Unassigned])
+            .with_strict();
+        let cls = DefaultClassifier;
+        let mut lexer = LexerStream::new(&cfg, &cls, Cursor::new("ab \u{378}c"));
+        let tokens: Vec<_> = lexer.by_ref().map(|t| t.text).collect();
+
+        assert_eq!(tokens, ["ab"]);
+        let err = lexer.error().unwrap();
+        assert_eq!(err.kind, LexErrorKind::InvalidChar('\u{378}'));
+        assert_eq!(err.offset, 3);
+        assert_eq!(err.to_string(), "invalid character U+0378 at offset 3");
+    }
+
+    #[test]
+    fn test_classifier_hook() {
+        struct NoDollar;
+        impl Classifier for NoDollar {
+            type Custom = ();
+            fn classify(&self, c: char) -> (BaseKind, Option<()>, Option<Cow<'static, str>>) {
+                match c {
+                    '$' => (BaseKind::Error, None, None),
+                    _ => (classify_base(c), None, None),
+                }
+            }
+        }
+
+        let cfg = Config::default();
+        let tokens: Vec<_> = LexerInline::new(&cfg, &NoDollar, "x$$y")
+            .map(|t| (t.kind.base, t.text))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                (BaseKind::Word, Cow::Borrowed("x")),
+                (BaseKind::Error, Cow::Borrowed("$$")),
+                (BaseKind::Word, Cow::Borrowed("y"))
+            ]
+        );
+    }
+}
@@ -0,0 +1,224 @@
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Overflow {
+    Split,
+    Error,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Limits {
+    pub max_token_len: Option<usize>,
+    pub overflow: Overflow,
+    pub max_tokens: Option<usize>,
+    pub max_input: Option<usize>,
+}
+
+impl Default for Limits {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            max_token_len: None,
+            overflow: Overflow::Split,
+            max_tokens: None,
+            max_input: None,
+        }
+    }
+}
+
+impl Limits {
+    #[inline]
+    pub fn max_token_len(mut self, bytes: usize, overflow: Overflow) -> Self {
+        self.max_token_len = Some(bytes.max(1));
+        self.overflow = overflow;
+        self
+    }
+
+    #[inline]
+    pub fn max_tokens(mut self, count: usize) -> Self {
+        self.max_tokens = Some(count);
+        self
+    }
+
+    #[inline]
+    pub fn max_input(mut self, bytes: usize) -> Self {
+        self.max_input = Some(bytes);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::classifier::{Classification, Classifier, DefaultClassifier};
+    use crate::lexer::config::Config;
+    use crate::lexer::error::LexErrorKind;
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::stream::LexerStream;
+    use crate::lexer::token::{classify_base, BaseKind, TokenKind};
+    use std::cell::Cell;
+    use std::io::{self, BufReader, Read};
+
+    struct Digits;
+    impl Read for Digits {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            buf.fill(b'7');
+            Ok(buf.len())
+        }
+    }
+
+    #[derive(Default)]
+    struct Counting {
+        calls: Cell<usize>,
+    }
+
+    impl Classifier for Counting {
+        type Custom = ();
+
+        fn classify(&self, c: char) -> Classification<()> {
+            self.calls.set(self.calls.get() + 1);
+            (classify_base(c), None, None)
+        }
+    }
+
+    #[test]
+    fn test_split_long_tokens() {
+        let cfg =
+            Config::default().with_limits(Limits::default().max_token_len(4, Overflow::Split));
+        let cls = DefaultClassifier;
+        let tokens: Vec<_> = LexerInline::new(&cfg, &cls, "abcdefghij ééé")
+            .map(|t| (t.text, t.start))
+            .collect();
+        assert_eq!(
+            tokens,
+            [
+                ("abcd".into(), 0),
+                ("efgh".into(), 4),
+                ("ij".into(), 8),
+                ("éé".into(), 11),
+                ("é".into(), 15)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unbounded_stream_token_errors() {
+        let cfg =
+            Config::default().with_limits(Limits::default().max_token_len(64, Overflow::Error));
+        let cls = DefaultClassifier;
+        let mut lexer = LexerStream::new(&cfg, &cls, BufReader::with_capacity(16, Digits));
+
+        assert!(lexer.next().is_none());
+        let err = lexer.error().unwrap();
+        assert_eq!(err.kind, LexErrorKind::TokenTooLong);
+        assert_eq!(err.offset, 0);
+    }
+
+    #[test]
+    fn test_unbounded_stream_is_linear() {
+        let len = 3 << 20;
+        let cfg = Config::default();
+        let cls = Counting::default();
+        let reader = BufReader::with_capacity(4096, Digits.take(len as u64));
+        let mut lexer = LexerStream::new(&cfg, &cls, reader);
+
+        let token = lexer.next().unwrap();
+        assert_eq!((token.start, token.end), (0, len));
+        assert!(lexer.next().is_none() && lexer.error().is_none());
+        assert_eq!(cls.calls.get(), len);
+    }
+
+    #[test]
+    fn test_unterminated_stream_rule_errors() {
+        let word = TokenKind::new(BaseKind::Word, None);
+        let cfg = Config::default()
+            .quoted("\"", "\"", Some('\\'), true, word)
+            .block_comment("(*", "*)", true, word)
+            .with_limits(Limits::default().max_token_len(64, Overflow::Error));
+        let cls = DefaultClassifier;
+        for open in ["\"", "(*"] {
+            let reader = BufReader::with_capacity(16, open.as_bytes().chain(Digits));
+            let mut lexer = LexerStream::new(&cfg, &cls, reader);
+
+            assert!(lexer.next().is_none());
+            let err = lexer.error().unwrap();
+            assert_eq!(err.kind, LexErrorKind::TokenTooLong);
+            assert_eq!(err.offset, 0);
+        }
+    }
+
+    #[test]
+    fn test_split_long_rule_tokens() {
+        let word = TokenKind::new(BaseKind::Word, None);
+        let cfg = Config::default()
+            .quoted("\"", "\"", Some('\\'), true, word)
+            .block_comment("(*", "*)", true, word)
+            .line_comment("#", word)
+            .with_limits(Limits::default().max_token_len(4, Overflow::Split));
+        let cls = DefaultClassifier;
+        let input = "\"ab\\\"cdé\" (*x(*y*)z*) #comment\nw";
+        let inline: Vec<_> = LexerInline::new(&cfg, &cls, input)
+            .map(|t| (t.text.into_owned(), t.start))
+            .collect();
+        assert_eq!(
+            inline,
+            [
+                ("\"ab".into(), 0),
+                ("\\\"cd".into(), 3),
+                ("é\"".into(), 7),
+                ("(*x".into(), 11),
+                ("(*y".into(), 14),
+                ("*)z".into(), 17),
+                ("*)".into(), 20),
+                ("#com".into(), 23),
+                ("ment".into(), 27),
+                ("w".into(), 32),
+            ]
+        );
+        for capacity in 1..=6 {
+            let reader = BufReader::with_capacity(capacity, input.as_bytes());
+            let stream: Vec<_> = LexerStream::new(&cfg, &cls, reader)
+                .map(|t| (t.text.into_owned(), t.start))
+                .collect();
+            assert_eq!(stream, inline);
+        }
+
+        let reader = BufReader::with_capacity(16, "\"".as_bytes().chain(Digits));
+        let lexer = LexerStream::new(&cfg, &cls, reader);
+        assert!(lexer.take(10_000).all(|t| t.text.len() <= 4));
+    }
+
+    #[test]
+    fn test_max_tokens() {
+        let cfg = Config::default().with_limits(Limits::default().max_tokens(2));
+        let cls = DefaultClassifier;
+        let mut lexer = LexerInline::new(&cfg, &cls, "a b c d");
+        let tokens: Vec<_> = lexer.by_ref().map(|t| t.text).collect();
+
+        assert_eq!(tokens, ["a", "b"]);
+        let err = lexer.error().unwrap();
+        assert_eq!(err.kind, LexErrorKind::TooManyTokens);
+        assert_eq!(err.offset, 4);
+    }
+
+    #[test]
+    fn test_max_input() {
+        let limits = Limits::default().max_input(1000);
+        let cfg = Config::default().with_limits(limits);
+        let cls = DefaultClassifier;
+
+        let reader = BufReader::with_capacity(16, Digits.take(4096));
+        let mut lexer = LexerStream::new(&cfg, &cls, reader);
+        assert!(lexer.next().is_none());
+        assert_eq!(lexer.error().unwrap().kind, LexErrorKind::InputTooLarge);
+        assert_eq!(lexer.error().unwrap().offset, 1000);
+
+        let input = "x ".repeat(600);
+        let mut lexer = LexerInline::new(&cfg, &cls, &input);
+        assert!(lexer.next().is_none());
+        assert_eq!(lexer.error().unwrap().kind, LexErrorKind::InputTooLarge);
+
+        let input = "x ".repeat(500);
+        let mut lexer = LexerStream::new(&cfg, &cls, input.as_bytes());
+        assert_eq!(lexer.by_ref().count(), 500);
+        assert!(lexer.error().is_none());
+    }
+}
@@ -1,11 +1,70 @@
+mod adapters;
+mod bytes;
+mod case;
+mod checkpoint;
 mod classifier;
+mod combine;
+mod compiled;
 mod config;
+mod context;
+mod cursor;
+mod decode;
+mod detok;
+mod error;
+mod grouping;
 mod inline;
+mod invalid;
+mod limits;
+mod normalize;
+mod offside;
+pub mod presets;
+mod replace;
+mod rules;
+mod scan;
+mod sourcemap;
+mod spec;
+mod stateful;
 mod stream;
+mod table;
 mod token;
+mod trivia;
+mod validate;
 
-pub use classifier::{Classifier, DefaultClassifier, NoCustom};
+pub use adapters::{DropTrivia, FilterKinds, MapText, MergeAdjacent, SplitOn, TokenIterExt};
+pub use bytes::{
+    ByteClassification, ByteClassifier, ByteToken, DefaultByteClassifier, LexerByteStream,
+    LexerBytes,
+};
+pub use case::fold_case;
+pub use checkpoint::Checkpoint;
+pub use classifier::{
+    Classification, Classifier, DefaultClassifier, DynClassifier, NoCustom, PartialClassifier,
+};
+pub use combine::{layer, Chain, Layer, MapCustom, Override, WithReplacements};
+pub use compiled::{CompiledConfig, KindIndex};
 pub use config::Config;
+pub use context::ContextClassifier;
+pub use cursor::{CursorError, Mark, TokenCursor};
+pub use decode::{Decoder, Encoding, OffsetMap};
+pub use detok::{reconstruct, render, Reconstruct, Spacing};
+pub use error::{LexError, LexErrorKind};
+pub use grouping::Grouping;
 pub use inline::LexerInline;
+pub use invalid::InvalidChar;
+pub use limits::{Limits, Overflow};
+pub use normalize::Normalization;
+pub use offside::Offside;
+pub use presets::{Preset, PresetClassifier};
+pub use replace::ReplaceMode;
+pub use rules::Rule;
+pub use sourcemap::SourceMap;
+pub use spec::{KindId, Spec, SpecError};
+pub use stateful::{Stateful, StatefulClassifier};
 pub use stream::LexerStream;
-pub use token::{BaseKind, Token, TokenKind};
+pub use table::{ClassifierBuilder, FnClassifier, TableClassifier};
+pub use token::{classify_base, is_line_break, BaseKind, Newlines, Token, TokenKind};
+pub use trivia::{Trivia, TriviaMode};
+pub use unicode_general_category::GeneralCategory;
+pub use validate::{Issue, Report, Severity};
+#[cfg(feature = "derive")]
+pub use xlex_derive::KindIndex;
@@ -0,0 +1,79 @@
+use std::borrow::Cow;
+
+use unicode_normalization::{
+    is_nfc_quick, is_nfd_quick, is_nfkc_quick, is_nfkd_quick, IsNormalized, UnicodeNormalization,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Normalization {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+impl Normalization {
+    pub fn apply<'a>(self, text: Cow<'a, str>) -> Cow<'a, str> {
+        if self.is_normalized(&text) {
+            return text;
+        }
+
+        let out: String = match self {
+            Normalization::Nfc => text.nfc().collect(),
+            Normalization::Nfd => text.nfd().collect(),
+            Normalization::Nfkc => text.nfkc().collect(),
+            Normalization::Nfkd => text.nfkd().collect(),
+        };
+        if out == text {
+            text
+        } else {
+            Cow::Owned(out)
+        }
+    }
+
+    #[inline]
+    fn is_normalized(self, text: &str) -> bool {
+        let quick = match self {
+            Normalization::Nfc => is_nfc_quick(text.chars()),
+            Normalization::Nfd => is_nfd_quick(text.chars()),
+            Normalization::Nfkc => is_nfkc_quick(text.chars()),
+            Normalization::Nfkd => is_nfkd_quick(text.chars()),
+        };
+        quick == IsNormalized::Yes
+    }
+}
+
+#[inline]
+pub(crate) fn is_combining_mark(c: char) -> bool {
+    unicode_normalization::char::is_combining_mark(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nfc_composes() {
+        let out = Normalization::Nfc.apply(Cow::Borrowed("e\u{301}"));
+        assert_eq!(out, "é");
+        assert!(matches!(out, Cow::Owned(_)));
+    }
+
+    #[test]
+    fn test_nfd_decomposes() {
+        let out = Normalization::Nfd.apply(Cow::Borrowed("é"));
+        assert_eq!(out, "e\u{301}");
+    }
+
+    #[test]
+    fn test_nfkc_folds_compatibility_forms() {
+        assert_eq!(Normalization::Nfkc.apply(Cow::Borrowed("ＡＢＣ")), "ABC");
+        assert_eq!(Normalization::Nfkc.apply(Cow::Borrowed("ﬁ")), "fi");
+    }
+
+    #[test]
+    fn test_unchanged_text_stays_borrowed() {
+        let out = Normalization::Nfkd.apply(Cow::Borrowed("plain"));
+        assert!(matches!(out, Cow::Borrowed("plain")));
+    }
+}
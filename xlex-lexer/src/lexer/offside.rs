@@ -0,0 +1,403 @@
+use super::error::{LexError, LexErrorKind};
+use super::token::is_line_break;
+
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Offside {
+    pub tab_width: usize,
+    pub allow_mixed: bool,
+    pub brackets: bool,
+}
+
+impl Default for Offside {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            tab_width: 8,
+            allow_mixed: false,
+            brackets: true,
+        }
+    }
+}
+
+impl Offside {
+    #[inline]
+    pub fn tab_width(mut self, width: usize) -> Self {
+        self.tab_width = width.max(1);
+        self
+    }
+
+    #[inline]
+    pub fn allow_mixed(mut self) -> Self {
+        self.allow_mixed = true;
+        self
+    }
+
+    #[inline]
+    pub fn ignore_brackets(mut self) -> Self {
+        self.brackets = false;
+        self
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct OffsideState {
+    levels: [u32; MAX_DEPTH],
+    depth: usize,
+
+    pub(crate) line_start: bool,
+    pub(crate) line_content: bool,
+    pub(crate) brackets: usize,
+
+    indent: bool,
+    dedents: usize,
+    at: usize,
+}
+
+impl Default for OffsideState {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            levels: [0; MAX_DEPTH],
+            depth: 0,
+            line_start: true,
+            line_content: false,
+            brackets: 0,
+            indent: false,
+            dedents: 0,
+            at: 0,
+        }
+    }
+}
+
+pub(crate) enum Measure {
+    More,
+    Done,
+}
+
+pub(crate) enum Synthetic {
+    Indent,
+    Dedent,
+    Newline,
+}
+
+impl OffsideState {
+    pub(crate) fn measure(
+        &mut self,
+        rules: &Offside,
+        rest: &str,
+        pos: usize,
+        more: bool,
+        comment: impl FnOnce(&str) -> Option<bool>,
+    ) -> Result<Measure, LexError> {
+        let mut column = 0usize;
+        let (mut spaces, mut tabs) = (false, false);
+        let mut len = 0;
+        for ch in rest.chars() {
+            match ch {
+                ' ' => {
+                    column += 1;
+                    spaces = true;
+                }
+                '\t' => {
+                    column += rules.tab_width - column % rules.tab_width;
+                    tabs = true;
+                }
+                _ => break,
+            }
+            len += 1;
+        }
+
+        match rest[len..].chars().next() {
+            None if more => return Ok(Measure::More),
+            None => {
+                self.line_start = false;
+                return Ok(Measure::Done);
+            }
+            Some(c) if is_line_break(c) => {
+                self.line_start = false;
+                return Ok(Measure::Done);
+            }
+            Some(_) => match comment(&rest[len..]) {
+                None => return Ok(Measure::More),
+                Some(true) => {
+                    self.line_start = false;
+                    return Ok(Measure::Done);
+                }
+                Some(false) => {}
+            },
+        }
+        if spaces && tabs && !rules.allow_mixed {
+            return Err(LexError::new(LexErrorKind::MixedIndentation, pos));
+        }
+
+        let at = pos + len;
+        let column = column as u32;
+        if column > self.top() {
+            if self.depth == MAX_DEPTH {
+                return Err(LexError::new(LexErrorKind::IndentTooDeep, at));
+            }
+            self.levels[self.depth] = column;
+            self.depth += 1;
+            self.indent = true;
+        } else {
+            while column < self.top() {
+                self.depth -= 1;
+                self.dedents += 1;
+            }
+            if column != self.top() {
+                return Err(LexError::new(LexErrorKind::InconsistentDedent, at));
+            }
+        }
+
+        self.at = at;
+        self.line_start = false;
+        Ok(Measure::Done)
+    }
+
+    #[inline]
+    pub(crate) fn pending(&mut self, pos: usize) -> Option<(Synthetic, usize)> {
+        if pos < self.at {
+            return None;
+        }
+        if self.dedents > 0 {
+            self.dedents -= 1;
+            return Some((Synthetic::Dedent, self.at));
+        }
+        if self.indent {
+            self.indent = false;
+            return Some((Synthetic::Indent, self.at));
+        }
+        None
+    }
+
+    #[inline]
+    pub(crate) fn finish(&mut self) -> Option<Synthetic> {
+        if self.line_content {
+            self.line_content = false;
+            return Some(Synthetic::Newline);
+        }
+        if self.depth > 0 {
+            self.depth -= 1;
+            return Some(Synthetic::Dedent);
+        }
+        None
+    }
+
+    pub(crate) fn track(&mut self, rules: &Offside, text: &str) {
+        self.line_content = true;
+        if !rules.brackets {
+            return;
+        }
+        for ch in text.chars() {
+            match ch {
+                '(' | '[' | '{' => self.brackets += 1,
+                ')' | ']' | '}' => self.brackets = self.brackets.saturating_sub(1),
+                _ => {}
+            }
+        }
+    }
+
+    #[inline]
+    fn top(&self) -> u32 {
+        match self.depth {
+            0 => 0,
+            d => self.levels[d - 1],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::classifier::DefaultClassifier;
+    use crate::lexer::config::Config;
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::stream::LexerStream;
+    use crate::lexer::token::{BaseKind, Token, TokenKind};
+    use std::io::{BufReader, Cursor};
+
+    fn show<TK: Copy + Eq + std::hash::Hash>(tokens: &[Token<'_, TK>]) -> Vec<String> {
+        tokens
+            .iter()
+            .map(|t| match t.kind.base {
+                BaseKind::Indent => format!("INDENT@{}", t.start),
+                BaseKind::Dedent => format!("DEDENT@{}", t.start),
+                BaseKind::Newline => format!("NEWLINE@{}", t.start),
+                _ => format!("{}@{}", t.text, t.start),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_indent_dedent_newline() {
+        let cfg = Config::default().with_offside(Offside::default());
+        let cls = DefaultClassifier;
+        let tokens: Vec<_> = LexerInline::new(&cfg, &cls, "if x:\n    y\n    z\nw\n").collect();
+        assert_eq!(
+            show(&tokens),
+            [
+                "if@0",
+                "x@3",
+                ":@4",
+                "NEWLINE@5",
+                "INDENT@10",
+                "y@10",
+                "NEWLINE@11",
+                "z@16",
+                "NEWLINE@17",
+                "DEDENT@18",
+                "w@18",
+                "NEWLINE@19"
+            ]
+        );
+        assert!(tokens
+            .iter()
+            .all(|t| t.kind.base != BaseKind::Indent || t.text.is_empty()));
+    }
+
+    #[test]
+    fn test_eof_closes_blocks_and_skips_blank_lines() {
+        let cfg = Config::default().with_offside(Offside::default());
+        let cls = DefaultClassifier;
+        let tokens: Vec<_> = LexerInline::new(&cfg, &cls, "a\n\n   \n  b\n\n  c").collect();
+        assert_eq!(
+            show(&tokens),
+            [
+                "a@0",
+                "NEWLINE@1",
+                "INDENT@9",
+                "b@9",
+                "NEWLINE@10",
+                "c@14",
+                "NEWLINE@15",
+                "DEDENT@15"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_brackets_suppress_layout() {
+        let cfg = Config::default().with_offside(Offside::default());
+        let cls = DefaultClassifier;
+        let tokens: Vec<_> = LexerInline::new(&cfg, &cls, "f(a,\n  b)\nc").collect();
+        assert_eq!(
+            show(&tokens),
+            [
+                "f@0",
+                "(@1",
+                "a@2",
+                ",@3",
+                "b@7",
+                ")@8",
+                "NEWLINE@9",
+                "c@10",
+                "NEWLINE@11"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tab_width() {
+        let input = "a\n\tb\n        c";
+        let cls = DefaultClassifier;
+
+        let cfg = Config::default().with_offside(Offside::default().allow_mixed());
+        let tokens: Vec<_> = LexerInline::new(&cfg, &cls, input).collect();
+        assert_eq!(
+            tokens
+                .iter()
+                .filter(|t| t.kind.base == BaseKind::Indent)
+                .count(),
+            1
+        );
+
+        let cfg = Config::default().with_offside(Offside::default().tab_width(4));
+        let tokens: Vec<_> = LexerInline::new(&cfg, &cls, input).collect();
+        assert_eq!(
+            tokens
+                .iter()
+                .filter(|t| t.kind.base == BaseKind::Indent)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_mixed_indentation_error() {
+        let cfg = Config::default().with_offside(Offside::default());
+        let cls = DefaultClassifier;
+        let mut lexer = LexerInline::new(&cfg, &cls, "a\n \tb");
+        let tokens: Vec<_> = lexer.by_ref().collect();
+
+        assert_eq!(show(&tokens), ["a@0", "NEWLINE@1"]);
+        let err = lexer.error().unwrap();
+        assert_eq!(err.kind, LexErrorKind::MixedIndentation);
+        assert_eq!(err.offset, 2);
+    }
+
+    #[test]
+    fn test_inconsistent_dedent_error() {
+        let cfg = Config::default().with_offside(Offside::default());
+        let cls = DefaultClassifier;
+        let mut lexer = LexerInline::new(&cfg, &cls, "a\n    b\n  c");
+        lexer.by_ref().for_each(drop);
+
+        let err = lexer.error().unwrap();
+        assert_eq!(err.kind, LexErrorKind::InconsistentDedent);
+        assert_eq!(err.offset, 10);
+    }
+
+    #[test]
+    fn test_stream_matches_inline_across_refills() {
+        let input = "def f():\n\tif x:\n\t\treturn 1\n\n\tpass\r\nend\n";
+        let cfg = Config::default().with_offside(Offside::default().tab_width(4));
+        let cls = DefaultClassifier;
+
+        let inline: Vec<_> = LexerInline::new(&cfg, &cls, input).collect();
+        let reader = BufReader::with_capacity(3, Cursor::new(input));
+        let stream: Vec<_> = LexerStream::new(&cfg, &cls, reader).collect();
+        assert_eq!(show(&inline), show(&stream));
+        assert_eq!(
+            inline
+                .iter()
+                .filter(|t| t.kind.base == BaseKind::Dedent)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_comment_lines_are_blank() {
+        let comment = TokenKind::new(BaseKind::Symbol, None);
+        let cfg = Config::default()
+            .with_offside(Offside::default())
+            .line_comment("//", comment);
+        let cls = DefaultClassifier;
+        let input = "a:\n  b\n// x\n      // y\n  c // z\n";
+        let inline: Vec<_> = LexerInline::new(&cfg, &cls, input).collect();
+        assert_eq!(
+            show(&inline),
+            [
+                "a@0",
+                ":@1",
+                "NEWLINE@2",
+                "INDENT@5",
+                "b@5",
+                "NEWLINE@6",
+                "// x@7",
+                "// y@18",
+                "c@25",
+                "// z@27",
+                "NEWLINE@31",
+                "DEDENT@32"
+            ]
+        );
+        for capacity in 1..=4 {
+            let reader = BufReader::with_capacity(capacity, Cursor::new(input));
+            let stream: Vec<_> = LexerStream::new(&cfg, &cls, reader).collect();
+            assert_eq!(show(&stream), show(&inline));
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use super::{Preset, PresetClassifier};
+use crate::lexer::config::Config;
+use crate::lexer::token::{BaseKind, TokenKind};
+use crate::lexer::KindIndex;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, KindIndex)]
+pub enum Kind {
+    Keyword,
+    String,
+    Char,
+    Number,
+    Punct,
+    Comment,
+    Preprocessor,
+}
+
+const KEYWORDS: [&str; 37] = [
+    "auto",
+    "break",
+    "case",
+    "char",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "typedef",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+    "_Bool",
+    "_Complex",
+    "_Imaginary",
+];
+
+const OPERATORS: [&str; 22] = [
+    "->", "++", "--", "==", "!=", "<=", ">=", "&&", "||", "<<", ">>", "+=", "-=", "*=", "/=", "%=",
+    "&=", "|=", "^=", "<<=", ">>=", "...",
+];
+
+pub fn preset() -> Preset<Kind> {
+    let comment = TokenKind::new(BaseKind::Symbol, Some(Kind::Comment));
+    let punct = TokenKind::new(BaseKind::Symbol, Some(Kind::Punct));
+    let config = Config::default()
+        .with_kind_index()
+        .merge(BaseKind::Word, BaseKind::Number)
+        .merge(BaseKind::Number, BaseKind::Word)
+        .line_comment("//", comment)
+        .block_comment("/*", "*/", false, comment)
+        .line_comment(
+            "#",
+            TokenKind::new(BaseKind::Symbol, Some(Kind::Preprocessor)),
+        )
+        .quoted(
+            "\"",
+            "\"",
+            Some('\\'),
+            false,
+            TokenKind::new(BaseKind::Word, Some(Kind::String)),
+        )
+        .quoted(
+            "'",
+            "'",
+            Some('\\'),
+            false,
+            TokenKind::new(BaseKind::Word, Some(Kind::Char)),
+        )
+        .keywords(KEYWORDS.into_iter().map(|k| (k, Kind::Keyword)));
+    let config = OPERATORS
+        .into_iter()
+        .fold(config, |config, op| config.literal(op, punct));
+    let classifier = PresetClassifier::code(Kind::Number, Kind::Punct, "(){}[];:,.<>=!&|+-*/%^?~");
+    Preset::new(config, classifier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"#include <stdio.h>
+/* entry */
+int main(void) {
+    char c = '\n';
+    return c >= 10 ? 0x1Fu : -1.0f;
+}"#;
+
+    #[test]
+    fn test_sample() {
+        let preset = preset();
+        let tokens: Vec<_> = preset
+            .lex(SAMPLE)
+            .map(|t| (t.text.into_owned(), t.kind.custom))
+            .collect();
+        let expect = [
+            ("#include <stdio.h>", Some(Kind::Preprocessor)),
+            ("/* entry */", Some(Kind::Comment)),
+            ("int", Some(Kind::Keyword)),
+            ("main", None),
+            ("(", Some(Kind::Punct)),
+            ("void", Some(Kind::Keyword)),
+            (")", Some(Kind::Punct)),
+            ("{", Some(Kind::Punct)),
+            ("char", Some(Kind::Keyword)),
+            ("c", None),
+            ("=", Some(Kind::Punct)),
+            ("'\\n'", Some(Kind::Char)),
+            (";", Some(Kind::Punct)),
+            ("return", Some(Kind::Keyword)),
+            ("c", None),
+            (">=", Some(Kind::Punct)),
+            ("10", Some(Kind::Number)),
+            ("?", Some(Kind::Punct)),
+            ("0x1Fu", Some(Kind::Number)),
+            (":", Some(Kind::Punct)),
+            ("-", Some(Kind::Punct)),
+            ("1.0f", Some(Kind::Number)),
+            (";", Some(Kind::Punct)),
+            ("}", Some(Kind::Punct)),
+        ];
+        let expect: Vec<_> = expect.iter().map(|&(t, k)| (t.to_string(), k)).collect();
+        assert_eq!(tokens, expect);
+    }
+
+    #[test]
+    fn test_validates() {
+        let report = preset().validate();
+        assert!(report.is_clean(), "{report}");
+    }
+}
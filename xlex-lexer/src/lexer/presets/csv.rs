@@ -0,0 +1,87 @@
+use super::{Preset, PresetClassifier};
+use crate::lexer::config::Config;
+use crate::lexer::table::ClassifierBuilder;
+use crate::lexer::token::{BaseKind, Newlines, TokenKind};
+use crate::lexer::KindIndex;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, KindIndex)]
+pub enum Kind {
+    String,
+    Number,
+    Punct,
+}
+
+pub fn preset() -> Preset<Kind> {
+    let config = Config::default()
+        .with_kind_index()
+        .with_newlines(Newlines::PerBreak)
+        .merge(BaseKind::Word, BaseKind::Number)
+        .merge(BaseKind::Number, BaseKind::Word)
+        .quoted(
+            "\"",
+            "\"",
+            Some('"'),
+            true,
+            TokenKind::new(BaseKind::Word, Some(Kind::String)),
+        );
+    let table = ClassifierBuilder::new()
+        .chars(
+            " \t!#$%&'()*+-./:;<=>?@[\\]^_`{|}~".chars(),
+            (BaseKind::Word, None, None),
+        )
+        .range('0'..='9', (BaseKind::Number, Some(Kind::Number), None))
+        .chars([','], (BaseKind::Symbol, Some(Kind::Punct), None))
+        .build();
+    Preset::new(config, PresetClassifier::new(table).signed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str =
+        "name,age,note\r\n\"Doe, John\",42,\"said \"\"hi\"\"\"\nJane Roe,-7.5,n/a\n";
+
+    #[test]
+    fn test_sample() {
+        let preset = preset();
+        let tokens: Vec<_> = preset
+            .lex(SAMPLE)
+            .map(|t| (t.text.into_owned(), t.kind.base, t.kind.custom))
+            .collect();
+        let field = |t: &str| (t.to_string(), BaseKind::Word, None);
+        let punct = || (",".to_string(), BaseKind::Symbol, Some(Kind::Punct));
+        let string = |t: &str| (t.to_string(), BaseKind::Word, Some(Kind::String));
+        let number = |t: &str| (t.to_string(), BaseKind::Number, Some(Kind::Number));
+        let newline = |t: &str| (t.to_string(), BaseKind::Newline, None);
+        assert_eq!(
+            tokens,
+            [
+                field("name"),
+                punct(),
+                field("age"),
+                punct(),
+                field("note"),
+                newline("\r\n"),
+                string("\"Doe, John\""),
+                punct(),
+                number("42"),
+                punct(),
+                string("\"said \"\"hi\"\"\""),
+                newline("\n"),
+                field("Jane Roe"),
+                punct(),
+                number("-7.5"),
+                punct(),
+                field("n/a"),
+                newline("\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validates() {
+        let report = preset().validate();
+        assert!(report.is_clean(), "{report}");
+    }
+}
@@ -0,0 +1,85 @@
+use super::{Preset, PresetClassifier};
+use crate::lexer::config::Config;
+use crate::lexer::token::{BaseKind, TokenKind};
+use crate::lexer::KindIndex;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, KindIndex)]
+pub enum Kind {
+    Keyword,
+    Section,
+    String,
+    Number,
+    Punct,
+    Comment,
+}
+
+pub fn preset() -> Preset<Kind> {
+    let comment = TokenKind::new(BaseKind::Symbol, Some(Kind::Comment));
+    let config = Config::default()
+        .with_kind_index()
+        .with_case_insensitive_keywords()
+        .merge(BaseKind::Word, BaseKind::Number)
+        .merge(BaseKind::Number, BaseKind::Word)
+        .line_comment(";", comment)
+        .line_comment("#", comment)
+        .quoted(
+            "[",
+            "]",
+            None,
+            false,
+            TokenKind::new(BaseKind::Word, Some(Kind::Section)),
+        )
+        .quoted(
+            "\"",
+            "\"",
+            Some('\\'),
+            false,
+            TokenKind::new(BaseKind::Word, Some(Kind::String)),
+        )
+        .keywords(
+            ["true", "false", "yes", "no", "on", "off"]
+                .into_iter()
+                .map(|k| (k, Kind::Keyword)),
+        );
+    let classifier = PresetClassifier::code(Kind::Number, Kind::Punct, "=:").signed();
+    Preset::new(config, classifier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str =
+        "; global\n[main section]\nname = \"John Doe\"\nport: -8080\nEnabled = Yes # on\n";
+
+    #[test]
+    fn test_sample() {
+        let preset = preset();
+        let tokens: Vec<_> = preset
+            .lex(SAMPLE)
+            .map(|t| (t.text.into_owned(), t.kind.custom))
+            .collect();
+        let expect = [
+            ("; global", Some(Kind::Comment)),
+            ("[main section]", Some(Kind::Section)),
+            ("name", None),
+            ("=", Some(Kind::Punct)),
+            ("\"John Doe\"", Some(Kind::String)),
+            ("port", None),
+            (":", Some(Kind::Punct)),
+            ("-8080", Some(Kind::Number)),
+            ("Enabled", None),
+            ("=", Some(Kind::Punct)),
+            ("Yes", Some(Kind::Keyword)),
+            ("# on", Some(Kind::Comment)),
+        ];
+        let expect: Vec<_> = expect.iter().map(|&(t, k)| (t.to_string(), k)).collect();
+        assert_eq!(tokens, expect);
+    }
+
+    #[test]
+    fn test_validates() {
+        let report = preset().validate();
+        assert!(report.is_clean(), "{report}");
+    }
+}
@@ -0,0 +1,73 @@
+use super::{Preset, PresetClassifier};
+use crate::lexer::config::Config;
+use crate::lexer::token::{BaseKind, TokenKind};
+use crate::lexer::KindIndex;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, KindIndex)]
+pub enum Kind {
+    Keyword,
+    String,
+    Number,
+    Punct,
+}
+
+pub fn preset() -> Preset<Kind> {
+    let config = Config::default()
+        .with_kind_index()
+        .merge(BaseKind::Word, BaseKind::Number)
+        .merge(BaseKind::Number, BaseKind::Word)
+        .quoted(
+            "\"",
+            "\"",
+            Some('\\'),
+            false,
+            TokenKind::new(BaseKind::Word, Some(Kind::String)),
+        )
+        .keywords([
+            ("true", Kind::Keyword),
+            ("false", Kind::Keyword),
+            ("null", Kind::Keyword),
+        ]);
+    let classifier = PresetClassifier::code(Kind::Number, Kind::Punct, "{}[]:,").signed();
+    Preset::new(config, classifier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"{"a": [1, -2.5e-3, true], "b\"": null}"#;
+
+    #[test]
+    fn test_sample() {
+        let preset = preset();
+        let tokens: Vec<_> = preset
+            .lex(SAMPLE)
+            .map(|t| (t.text.into_owned(), t.kind.custom))
+            .collect();
+        let expect = [
+            ("{", Some(Kind::Punct)),
+            ("\"a\"", Some(Kind::String)),
+            (":", Some(Kind::Punct)),
+            ("[", Some(Kind::Punct)),
+            ("1", Some(Kind::Number)),
+            (",", Some(Kind::Punct)),
+            ("-2.5e-3", Some(Kind::Number)),
+            (",", Some(Kind::Punct)),
+            ("true", Some(Kind::Keyword)),
+            ("]", Some(Kind::Punct)),
+            (",", Some(Kind::Punct)),
+            ("\"b\\\"\"", Some(Kind::String)),
+            (":", Some(Kind::Punct)),
+            ("null", Some(Kind::Keyword)),
+            ("}", Some(Kind::Punct)),
+        ];
+        let expect: Vec<_> = expect.iter().map(|&(t, k)| (t.to_string(), k)).collect();
+        assert_eq!(tokens, expect);
+    }
+
+    #[test]
+    fn test_validates() {
+        assert!(!preset().validate().has_errors());
+    }
+}
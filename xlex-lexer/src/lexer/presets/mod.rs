@@ -0,0 +1,207 @@
+#[cfg(feature = "preset-c")]
+pub mod c;
+#[cfg(feature = "preset-csv")]
+pub mod csv;
+#[cfg(feature = "preset-ini")]
+pub mod ini;
+#[cfg(feature = "preset-json")]
+pub mod json;
+#[cfg(feature = "preset-python")]
+pub mod python;
+#[cfg(feature = "preset-rust")]
+pub mod rust;
+#[cfg(feature = "preset-shell")]
+pub mod shell;
+#[cfg(feature = "preset-sql")]
+pub mod sql;
+#[cfg(feature = "preset-toml")]
+pub mod toml;
+
+use super::classifier::{Classification, Classifier};
+use super::config::Config;
+use super::context::ContextClassifier;
+use super::inline::LexerInline;
+use super::table::{ClassifierBuilder, TableClassifier};
+use super::token::BaseKind;
+use super::validate::Report;
+
+use std::hash::Hash;
+
+pub struct Preset<TK: Copy + Eq + Hash> {
+    pub config: Config<TK>,
+    pub classifier: PresetClassifier<TK>,
+}
+
+impl<TK: Copy + Eq + Hash> Preset<TK> {
+    #[inline]
+    pub fn new(config: Config<TK>, classifier: PresetClassifier<TK>) -> Self {
+        Self { config, classifier }
+    }
+
+    #[inline]
+    pub fn lex<'a>(&'a self, input: &'a str) -> LexerInline<'a, TK, PresetClassifier<TK>> {
+        LexerInline::new(&self.config, &self.classifier, input)
+    }
+
+    #[inline]
+    pub fn validate(&self) -> Report<TK> {
+        self.config.validate(&self.classifier)
+    }
+}
+
+pub struct PresetClassifier<TK: Copy + Eq + Hash> {
+    table: TableClassifier<TK>,
+    number: Option<TK>,
+    signed: bool,
+}
+
+impl<TK: Copy + Eq + Hash> PresetClassifier<TK> {
+    #[inline]
+    pub fn new(table: TableClassifier<TK>) -> Self {
+        Self {
+            number: table.classify('0').1,
+            table,
+            signed: false,
+        }
+    }
+
+    pub fn code(number: TK, punct: TK, symbols: &str) -> Self {
+        let table = ClassifierBuilder::new()
+            .chars(['_'], (BaseKind::Word, None, None))
+            .range('0'..='9', (BaseKind::Number, Some(number), None))
+            .chars(symbols.chars(), (BaseKind::Symbol, Some(punct), None))
+            .build();
+        Self::new(table)
+    }
+
+    #[inline]
+    pub fn signed(mut self) -> Self {
+        self.signed = true;
+        self
+    }
+}
+
+impl<TK: Copy + Eq + Hash> ContextClassifier for PresetClassifier<TK> {
+    type Custom = TK;
+    type State = bool;
+    type Snapshot = bool;
+
+    #[inline]
+    fn lookahead(&self) -> usize {
+        1
+    }
+
+    #[inline]
+    fn start(&self) -> bool {
+        false
+    }
+
+    fn classify_at(
+        &self,
+        in_number: &mut bool,
+        prev: Option<char>,
+        c: char,
+        next: &str,
+    ) -> Classification<TK> {
+        let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let digit = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit());
+        let next = next.chars().next();
+        let number = *in_number;
+        let class = match c {
+            '.' if number && digit(prev) && digit(next) => (BaseKind::Number, self.number, None),
+            '+' | '-' if number && matches!(prev, Some('e' | 'E')) && digit(next) => {
+                (BaseKind::Number, self.number, None)
+            }
+            '+' | '-' if self.signed && !number && !word(prev) && digit(next) => {
+                (BaseKind::Number, self.number, None)
+            }
+            c => self.table.classify(c),
+        };
+        *in_number = match class.0 {
+            BaseKind::Number | BaseKind::Word if number => true,
+            BaseKind::Number => !word(prev),
+            _ => false,
+        };
+        class
+    }
+
+    #[inline]
+    fn snapshot(&self, in_number: &bool) -> bool {
+        *in_number
+    }
+
+    #[inline]
+    fn restore(&self, in_number: &mut bool, snapshot: bool) {
+        *in_number = snapshot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::stream::LexerStream;
+    use crate::lexer::token::TokenKind;
+    use std::io::{BufReader, Cursor};
+
+    fn preset() -> Preset<u8> {
+        let config = Config::default()
+            .merge(BaseKind::Word, BaseKind::Number)
+            .merge(BaseKind::Number, BaseKind::Word);
+        Preset::new(config, PresetClassifier::code(0, 1, "+-.,").signed())
+    }
+
+    #[test]
+    fn test_numbers_in_context() {
+        let preset = preset();
+        let tokens: Vec<_> = preset
+            .lex("-1.5e-3 x1.2 0xFFu8 a-1, 2.x")
+            .map(|t| (t.text.into_owned(), t.kind))
+            .collect();
+        let number = TokenKind::new(BaseKind::Number, Some(0));
+        let punct = TokenKind::new(BaseKind::Symbol, Some(1));
+        assert_eq!(
+            tokens,
+            [
+                ("-1.5e-3".into(), number),
+                ("x1".into(), TokenKind::WORD),
+                (".".into(), punct),
+                ("2".into(), number),
+                ("0xFFu8".into(), number),
+                ("a".into(), TokenKind::WORD),
+                ("-".into(), punct),
+                ("1".into(), number),
+                (",".into(), punct),
+                ("2".into(), number),
+                (".".into(), punct),
+                ("x".into(), TokenKind::WORD),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexers_do_not_share_number_state() {
+        fn sync<T: Sync>(_: &T) {}
+        let preset = preset();
+        sync(&preset);
+        let mut first = preset.lex("1, 2");
+        let mut second = preset.lex("-1");
+        assert_eq!(first.next().unwrap().text, "1");
+        assert_eq!(second.next().unwrap().text, "-1");
+        assert_eq!(first.next().unwrap().text, ",");
+    }
+
+    #[test]
+    fn test_stream_matches_inline() {
+        let preset = preset();
+        let input = "-1.5e-3 x1.2 0xFFu8 a-1, 2.x";
+        let reader = BufReader::with_capacity(1, Cursor::new(input));
+        let stream: Vec<_> = LexerStream::new(&preset.config, &preset.classifier, reader)
+            .map(|t| (t.text.into_owned(), t.kind))
+            .collect();
+        let inline: Vec<_> = preset
+            .lex(input)
+            .map(|t| (t.text.into_owned(), t.kind))
+            .collect();
+        assert_eq!(stream, inline);
+    }
+}
@@ -0,0 +1,150 @@
+use super::{Preset, PresetClassifier};
+use crate::lexer::config::Config;
+use crate::lexer::offside::Offside;
+use crate::lexer::token::{BaseKind, TokenKind};
+use crate::lexer::KindIndex;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, KindIndex)]
+pub enum Kind {
+    Keyword,
+    String,
+    Number,
+    Punct,
+    Comment,
+}
+
+const KEYWORDS: [&str; 35] = [
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+const OPERATORS: [&str; 12] = [
+    "**", "//", "==", "!=", "<=", ">=", "->", ":=", "+=", "-=", "*=", "/=",
+];
+
+const PREFIXES: [&str; 9] = ["", "r", "u", "b", "f", "br", "rb", "fr", "rf"];
+
+fn cases(prefix: &str) -> impl Iterator<Item = String> + '_ {
+    (0..1 << prefix.len()).map(move |mask| {
+        prefix
+            .chars()
+            .enumerate()
+            .map(|(i, c)| match mask >> i & 1 {
+                1 => c.to_ascii_uppercase(),
+                _ => c,
+            })
+            .collect()
+    })
+}
+
+pub fn preset() -> Preset<Kind> {
+    let string = TokenKind::new(BaseKind::Word, Some(Kind::String));
+    let punct = TokenKind::new(BaseKind::Symbol, Some(Kind::Punct));
+    let mut config = Config::default()
+        .with_kind_index()
+        .with_offside(Offside::default())
+        .merge(BaseKind::Word, BaseKind::Number)
+        .merge(BaseKind::Number, BaseKind::Word)
+        .line_comment("#", TokenKind::new(BaseKind::Symbol, Some(Kind::Comment)))
+        .keywords(KEYWORDS.into_iter().map(|k| (k, Kind::Keyword)));
+    for prefix in PREFIXES.into_iter().flat_map(cases) {
+        for quote in ["\"\"\"", "'''", "\"", "'"] {
+            let open = format!("{prefix}{quote}");
+            config = config.quoted(&open, quote, Some('\\'), quote.len() == 3, string);
+        }
+    }
+    let config = OPERATORS
+        .into_iter()
+        .fold(config, |config, op| config.literal(op, punct));
+    let classifier = PresetClassifier::code(Kind::Number, Kind::Punct, "(){}[];:,.<>=!&|+-*/%^@~");
+    Preset::new(config, classifier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str =
+        "def f(x):\n    y = x\n# note\n  # note\n    return y ** 2.5  # sq\nprint(f\"{f(3)}\", 'a\\'b', \"\"\"\ndoc\"\"\")\n";
+
+    #[test]
+    fn test_sample() {
+        let preset = preset();
+        let tokens: Vec<_> = preset
+            .lex(SAMPLE)
+            .map(|t| match t.kind.base {
+                BaseKind::Indent => ("<indent>".to_string(), None),
+                BaseKind::Dedent => ("<dedent>".to_string(), None),
+                BaseKind::Newline => ("<newline>".to_string(), None),
+                _ => (t.text.into_owned(), t.kind.custom),
+            })
+            .collect();
+        let expect = [
+            ("def", Some(Kind::Keyword)),
+            ("f", None),
+            ("(", Some(Kind::Punct)),
+            ("x", None),
+            (")", Some(Kind::Punct)),
+            (":", Some(Kind::Punct)),
+            ("<newline>", None),
+            ("<indent>", None),
+            ("y", None),
+            ("=", Some(Kind::Punct)),
+            ("x", None),
+            ("<newline>", None),
+            ("# note", Some(Kind::Comment)),
+            ("# note", Some(Kind::Comment)),
+            ("return", Some(Kind::Keyword)),
+            ("y", None),
+            ("**", Some(Kind::Punct)),
+            ("2.5", Some(Kind::Number)),
+            ("# sq", Some(Kind::Comment)),
+            ("<newline>", None),
+            ("<dedent>", None),
+            ("print", None),
+            ("(", Some(Kind::Punct)),
+            ("f\"{f(3)}\"", Some(Kind::String)),
+            (",", Some(Kind::Punct)),
+            ("'a\\'b'", Some(Kind::String)),
+            (",", Some(Kind::Punct)),
+            ("\"\"\"\ndoc\"\"\"", Some(Kind::String)),
+            (")", Some(Kind::Punct)),
+            ("<newline>", None),
+        ];
+        let expect: Vec<_> = expect.iter().map(|&(t, k)| (t.to_string(), k)).collect();
+        assert_eq!(tokens, expect);
+    }
+
+    #[test]
+    fn test_string_prefixes() {
+        let preset = preset();
+        let input = "rb'x' bR\"y\" Fr'z' F'y' R\"a\\\"b\" u'q' RB'''m''' bu'v'";
+        let tokens: Vec<_> = preset
+            .lex(input)
+            .map(|t| (t.text.into_owned(), t.kind.custom))
+            .collect();
+        let string = Some(Kind::String);
+        let expect = [
+            ("rb'x'", string),
+            ("bR\"y\"", string),
+            ("Fr'z'", string),
+            ("F'y'", string),
+            ("R\"a\\\"b\"", string),
+            ("u'q'", string),
+            ("RB'''m'''", string),
+            ("bu", None),
+            ("'v'", string),
+            ("", None),
+        ];
+        let expect: Vec<_> = expect.iter().map(|&(t, k)| (t.to_string(), k)).collect();
+        assert_eq!(tokens, expect);
+    }
+
+    #[test]
+    fn test_validates() {
+        let report = preset().validate();
+        assert!(report.is_clean(), "{report}");
+    }
+}
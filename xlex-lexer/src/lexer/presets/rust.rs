@@ -0,0 +1,154 @@
+use super::{Preset, PresetClassifier};
+use crate::lexer::config::Config;
+use crate::lexer::table::ClassifierBuilder;
+use crate::lexer::token::{BaseKind, TokenKind};
+use crate::lexer::KindIndex;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, KindIndex)]
+pub enum Kind {
+    Keyword,
+    String,
+    Number,
+    Punct,
+    Comment,
+}
+
+const KEYWORDS: [&str; 39] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "yield",
+];
+
+const OPERATORS: [&str; 20] = [
+    "::", "->", "=>", "==", "!=", "<=", ">=", "&&", "||", "+=", "-=", "*=", "/=", "%=", "^=", "&=",
+    "|=", "..", "..=", "...",
+];
+
+pub fn preset() -> Preset<Kind> {
+    let comment = TokenKind::new(BaseKind::Symbol, Some(Kind::Comment));
+    let string = TokenKind::new(BaseKind::Word, Some(Kind::String));
+    let punct = TokenKind::new(BaseKind::Symbol, Some(Kind::Punct));
+    let config = Config::default()
+        .with_kind_index()
+        .merge(BaseKind::Word, BaseKind::Number)
+        .merge(BaseKind::Number, BaseKind::Word)
+        .line_comment("//", comment)
+        .block_comment("/*", "*/", true, comment)
+        .quoted("\"", "\"", Some('\\'), true, string)
+        .quoted("b\"", "\"", Some('\\'), true, string)
+        .quoted("c\"", "\"", Some('\\'), true, string)
+        .raw_quoted("r", '#', '"', string)
+        .raw_quoted("br", '#', '"', string)
+        .raw_quoted("cr", '#', '"', string)
+        .char_literal("'", '\'', Some('\\'), string)
+        .char_literal("b'", '\'', Some('\\'), string)
+        .keywords(KEYWORDS.into_iter().map(|k| (k, Kind::Keyword)));
+    let config = OPERATORS
+        .into_iter()
+        .fold(config, |config, op| config.literal(op, punct));
+    let table = ClassifierBuilder::new()
+        .chars(['_', '\''], (BaseKind::Word, None, None))
+        .range('0'..='9', (BaseKind::Number, Some(Kind::Number), None))
+        .chars(
+            "(){}[];:,.<>=!&|+-*/%^@#?~$".chars(),
+            (BaseKind::Symbol, Some(Kind::Punct), None),
+        )
+        .build();
+    Preset::new(config, PresetClassifier::new(table))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r###"fn main() -> u32 {
+    // answer
+    let x = 0x2A_u32 + 1.5e3 as u32; /* a /* b */ */
+    println!("{x}\n", r#"raw "q""#);
+    let c = '"'; let d = b'\''; let e = r##"a "# b"##;
+    'outer: for s in ["x"] { let f: &'static str = s; }
+    x
+}"###;
+
+    #[test]
+    fn test_sample() {
+        let preset = preset();
+        let tokens: Vec<_> = preset
+            .lex(SAMPLE)
+            .map(|t| (t.text.into_owned(), t.kind.custom))
+            .collect();
+        let expect = [
+            ("fn", Some(Kind::Keyword)),
+            ("main", None),
+            ("(", Some(Kind::Punct)),
+            (")", Some(Kind::Punct)),
+            ("->", Some(Kind::Punct)),
+            ("u32", None),
+            ("{", Some(Kind::Punct)),
+            ("// answer", Some(Kind::Comment)),
+            ("let", Some(Kind::Keyword)),
+            ("x", None),
+            ("=", Some(Kind::Punct)),
+            ("0x2A_u32", Some(Kind::Number)),
+            ("+", Some(Kind::Punct)),
+            ("1.5e3", Some(Kind::Number)),
+            ("as", Some(Kind::Keyword)),
+            ("u32", None),
+            (";", Some(Kind::Punct)),
+            ("/* a /* b */ */", Some(Kind::Comment)),
+            ("println", None),
+            ("!", Some(Kind::Punct)),
+            ("(", Some(Kind::Punct)),
+            ("\"{x}\\n\"", Some(Kind::String)),
+            (",", Some(Kind::Punct)),
+            ("r#\"raw \"q\"\"#", Some(Kind::String)),
+            (")", Some(Kind::Punct)),
+            (";", Some(Kind::Punct)),
+            ("let", Some(Kind::Keyword)),
+            ("c", None),
+            ("=", Some(Kind::Punct)),
+            ("'\"'", Some(Kind::String)),
+            (";", Some(Kind::Punct)),
+            ("let", Some(Kind::Keyword)),
+            ("d", None),
+            ("=", Some(Kind::Punct)),
+            ("b'\\''", Some(Kind::String)),
+            (";", Some(Kind::Punct)),
+            ("let", Some(Kind::Keyword)),
+            ("e", None),
+            ("=", Some(Kind::Punct)),
+            ("r##\"a \"# b\"##", Some(Kind::String)),
+            (";", Some(Kind::Punct)),
+            ("'outer", None),
+            (":", Some(Kind::Punct)),
+            ("for", Some(Kind::Keyword)),
+            ("s", None),
+            ("in", Some(Kind::Keyword)),
+            ("[", Some(Kind::Punct)),
+            ("\"x\"", Some(Kind::String)),
+            ("]", Some(Kind::Punct)),
+            ("{", Some(Kind::Punct)),
+            ("let", Some(Kind::Keyword)),
+            ("f", None),
+            (":", Some(Kind::Punct)),
+            ("&", Some(Kind::Punct)),
+            ("'static", None),
+            ("str", None),
+            ("=", Some(Kind::Punct)),
+            ("s", None),
+            (";", Some(Kind::Punct)),
+            ("}", Some(Kind::Punct)),
+            ("x", None),
+            ("}", Some(Kind::Punct)),
+        ];
+        let expect: Vec<_> = expect.iter().map(|&(t, k)| (t.to_string(), k)).collect();
+        assert_eq!(tokens, expect);
+    }
+
+    #[test]
+    fn test_validates() {
+        let report = preset().validate();
+        assert!(report.is_clean(), "{report}");
+    }
+}
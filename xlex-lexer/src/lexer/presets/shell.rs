@@ -0,0 +1,127 @@
+use super::{Preset, PresetClassifier};
+use crate::lexer::config::Config;
+use crate::lexer::table::ClassifierBuilder;
+use crate::lexer::token::{BaseKind, TokenKind};
+use crate::lexer::KindIndex;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, KindIndex)]
+pub enum Kind {
+    Keyword,
+    String,
+    Variable,
+    Number,
+    Punct,
+    Comment,
+}
+
+const KEYWORDS: [&str; 16] = [
+    "case", "do", "done", "elif", "else", "esac", "fi", "for", "function", "if", "in", "select",
+    "then", "time", "until", "while",
+];
+
+const OPERATORS: [&str; 6] = ["&&", "||", ";;", ">>", "<<", "|&"];
+
+const SPECIAL: [&str; 7] = ["$#", "$?", "$@", "$$", "$!", "$*", "$-"];
+
+/// Quotes may start mid-word as in the shell, so an unbalanced `'` or `"`
+/// (as in `echo it's`) stops the lexer with an `Unterminated` error at the
+/// quote; the tokens before it are still produced.
+pub fn preset() -> Preset<Kind> {
+    let punct = TokenKind::new(BaseKind::Symbol, Some(Kind::Punct));
+    let variable = TokenKind::new(BaseKind::Word, Some(Kind::Variable));
+    let string = TokenKind::new(BaseKind::Word, Some(Kind::String));
+    let config = Config::default()
+        .with_kind_index()
+        .merge(BaseKind::Word, BaseKind::Word)
+        .merge(BaseKind::Word, BaseKind::Number)
+        .merge(BaseKind::Number, BaseKind::Word)
+        .standalone_comment("#", TokenKind::new(BaseKind::Symbol, Some(Kind::Comment)))
+        .quoted("\"", "\"", Some('\\'), true, string)
+        .quoted("'", "'", None, true, string)
+        .keywords(KEYWORDS.into_iter().map(|k| (k, Kind::Keyword)));
+    let config = OPERATORS
+        .into_iter()
+        .fold(config, |config, op| config.literal(op, punct));
+    let config = SPECIAL
+        .into_iter()
+        .fold(config, |config, var| config.literal(var, variable));
+    let table = ClassifierBuilder::new()
+        .chars(['_', '#'], (BaseKind::Word, None, None))
+        .chars(['$'], (BaseKind::Word, Some(Kind::Variable), None))
+        .range('0'..='9', (BaseKind::Number, Some(Kind::Number), None))
+        .chars(
+            "|&;<>(){}[]=!".chars(),
+            (BaseKind::Symbol, Some(Kind::Punct), None),
+        )
+        .build();
+    Preset::new(config, PresetClassifier::new(table))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::error::LexErrorKind;
+
+    const SAMPLE: &str =
+        "# greet\nif [ \"$1\" = 'x' ]; then\n  echo \"hi $USER\" $HOME $# && exit 0\nfi\necho a#b; x=1 # c\n";
+
+    #[test]
+    fn test_sample() {
+        let preset = preset();
+        let tokens: Vec<_> = preset
+            .lex(SAMPLE)
+            .map(|t| (t.text.into_owned(), t.kind.custom))
+            .collect();
+        let expect = [
+            ("# greet", Some(Kind::Comment)),
+            ("if", Some(Kind::Keyword)),
+            ("[", Some(Kind::Punct)),
+            ("\"$1\"", Some(Kind::String)),
+            ("=", Some(Kind::Punct)),
+            ("'x'", Some(Kind::String)),
+            ("]", Some(Kind::Punct)),
+            (";", Some(Kind::Punct)),
+            ("then", Some(Kind::Keyword)),
+            ("echo", None),
+            ("\"hi $USER\"", Some(Kind::String)),
+            ("$HOME", Some(Kind::Variable)),
+            ("$#", Some(Kind::Variable)),
+            ("&&", Some(Kind::Punct)),
+            ("exit", None),
+            ("0", Some(Kind::Number)),
+            ("fi", Some(Kind::Keyword)),
+            ("echo", None),
+            ("a#b", None),
+            (";", Some(Kind::Punct)),
+            ("x", None),
+            ("=", Some(Kind::Punct)),
+            ("1", Some(Kind::Number)),
+            ("# c", Some(Kind::Comment)),
+        ];
+        let expect: Vec<_> = expect.iter().map(|&(t, k)| (t.to_string(), k)).collect();
+        assert_eq!(tokens, expect);
+    }
+
+    #[test]
+    fn test_unbalanced_quote() {
+        let preset = preset();
+        let mut lexer = preset.lex("echo it's fine\nls\n");
+        let texts: Vec<_> = lexer.by_ref().map(|t| t.text.into_owned()).collect();
+        assert_eq!(texts, ["echo", "it"]);
+        let err = lexer.error().unwrap();
+        assert_eq!(err.kind, LexErrorKind::Unterminated);
+        assert_eq!(err.offset, 7);
+
+        let texts: Vec<_> = preset
+            .lex("echo it's' x")
+            .map(|t| t.text.into_owned())
+            .collect();
+        assert_eq!(texts, ["echo", "it", "'s'", "x"]);
+    }
+
+    #[test]
+    fn test_validates() {
+        let report = preset().validate();
+        assert!(report.is_clean(), "{report}");
+    }
+}
@@ -0,0 +1,98 @@
+use super::{Preset, PresetClassifier};
+use crate::lexer::config::Config;
+use crate::lexer::token::{BaseKind, TokenKind};
+use crate::lexer::KindIndex;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, KindIndex)]
+pub enum Kind {
+    Keyword,
+    String,
+    Identifier,
+    Number,
+    Punct,
+    Comment,
+}
+
+const KEYWORDS: [&str; 48] = [
+    "add", "all", "alter", "and", "as", "asc", "between", "by", "case", "create", "delete", "desc",
+    "distinct", "drop", "else", "end", "exists", "false", "from", "group", "having", "in", "index",
+    "inner", "insert", "into", "is", "join", "key", "left", "like", "limit", "not", "null", "on",
+    "or", "order", "primary", "select", "set", "table", "then", "true", "union", "update",
+    "values", "when", "where",
+];
+
+const OPERATORS: [&str; 6] = ["<=", ">=", "<>", "!=", "||", "::"];
+
+pub fn preset() -> Preset<Kind> {
+    let comment = TokenKind::new(BaseKind::Symbol, Some(Kind::Comment));
+    let punct = TokenKind::new(BaseKind::Symbol, Some(Kind::Punct));
+    let config = Config::default()
+        .with_kind_index()
+        .with_case_insensitive_keywords()
+        .merge(BaseKind::Word, BaseKind::Number)
+        .merge(BaseKind::Number, BaseKind::Word)
+        .line_comment("--", comment)
+        .block_comment("/*", "*/", false, comment)
+        .quoted(
+            "'",
+            "'",
+            Some('\''),
+            true,
+            TokenKind::new(BaseKind::Word, Some(Kind::String)),
+        )
+        .quoted(
+            "\"",
+            "\"",
+            Some('"'),
+            false,
+            TokenKind::new(BaseKind::Word, Some(Kind::Identifier)),
+        )
+        .keywords(KEYWORDS.into_iter().map(|k| (k, Kind::Keyword)));
+    let config = OPERATORS
+        .into_iter()
+        .fold(config, |config, op| config.literal(op, punct));
+    let classifier = PresetClassifier::code(Kind::Number, Kind::Punct, "(),;.*=<>+-/%!|:");
+    Preset::new(config, classifier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str =
+        "SELECT name, 'it''s' AS \"Quote\" -- note\nFROM t /* x */ WHERE id >= 10.5;";
+
+    #[test]
+    fn test_sample() {
+        let preset = preset();
+        let tokens: Vec<_> = preset
+            .lex(SAMPLE)
+            .map(|t| (t.text.into_owned(), t.kind.custom))
+            .collect();
+        let expect = [
+            ("SELECT", Some(Kind::Keyword)),
+            ("name", None),
+            (",", Some(Kind::Punct)),
+            ("'it''s'", Some(Kind::String)),
+            ("AS", Some(Kind::Keyword)),
+            ("\"Quote\"", Some(Kind::Identifier)),
+            ("-- note", Some(Kind::Comment)),
+            ("FROM", Some(Kind::Keyword)),
+            ("t", None),
+            ("/* x */", Some(Kind::Comment)),
+            ("WHERE", Some(Kind::Keyword)),
+            ("id", None),
+            (">=", Some(Kind::Punct)),
+            ("10.5", Some(Kind::Number)),
+            (";", Some(Kind::Punct)),
+        ];
+        let expect: Vec<_> = expect.iter().map(|&(t, k)| (t.to_string(), k)).collect();
+        assert_eq!(tokens, expect);
+    }
+
+    #[test]
+    fn test_validates() {
+        let report = preset().validate();
+        assert!(report.is_clean(), "{report}");
+    }
+}
@@ -0,0 +1,94 @@
+use super::{Preset, PresetClassifier};
+use crate::lexer::config::Config;
+use crate::lexer::table::ClassifierBuilder;
+use crate::lexer::token::{BaseKind, TokenKind};
+use crate::lexer::KindIndex;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, KindIndex)]
+pub enum Kind {
+    Keyword,
+    String,
+    Number,
+    Punct,
+    Comment,
+}
+
+pub fn preset() -> Preset<Kind> {
+    let string = TokenKind::new(BaseKind::Word, Some(Kind::String));
+    let config = Config::default()
+        .with_kind_index()
+        .merge(BaseKind::Word, BaseKind::Number)
+        .merge(BaseKind::Number, BaseKind::Word)
+        .line_comment("#", TokenKind::new(BaseKind::Symbol, Some(Kind::Comment)))
+        .quoted("\"\"\"", "\"\"\"", Some('\\'), true, string)
+        .quoted("'''", "'''", None, true, string)
+        .quoted("\"", "\"", Some('\\'), false, string)
+        .quoted("'", "'", None, false, string)
+        .keywords([("true", Kind::Keyword), ("false", Kind::Keyword)]);
+    let table = ClassifierBuilder::new()
+        .chars(['_', '-'], (BaseKind::Word, None, None))
+        .range('0'..='9', (BaseKind::Number, Some(Kind::Number), None))
+        .chars(
+            "[]{}=,.".chars(),
+            (BaseKind::Symbol, Some(Kind::Punct), None),
+        )
+        .build();
+    Preset::new(config, PresetClassifier::new(table).signed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"# server
+[server]
+host = "local\"host"
+ports = [8080, -1]
+ratio = +0.5
+bare-key = 'C:\path'
+text = """
+two"""
+"#;
+
+    #[test]
+    fn test_sample() {
+        let preset = preset();
+        let tokens: Vec<_> = preset
+            .lex(SAMPLE)
+            .map(|t| (t.text.into_owned(), t.kind.custom))
+            .collect();
+        let expect = [
+            ("# server", Some(Kind::Comment)),
+            ("[", Some(Kind::Punct)),
+            ("server", None),
+            ("]", Some(Kind::Punct)),
+            ("host", None),
+            ("=", Some(Kind::Punct)),
+            ("\"local\\\"host\"", Some(Kind::String)),
+            ("ports", None),
+            ("=", Some(Kind::Punct)),
+            ("[", Some(Kind::Punct)),
+            ("8080", Some(Kind::Number)),
+            (",", Some(Kind::Punct)),
+            ("-1", Some(Kind::Number)),
+            ("]", Some(Kind::Punct)),
+            ("ratio", None),
+            ("=", Some(Kind::Punct)),
+            ("+0.5", Some(Kind::Number)),
+            ("bare-key", None),
+            ("=", Some(Kind::Punct)),
+            ("'C:\\path'", Some(Kind::String)),
+            ("text", None),
+            ("=", Some(Kind::Punct)),
+            ("\"\"\"\ntwo\"\"\"", Some(Kind::String)),
+        ];
+        let expect: Vec<_> = expect.iter().map(|&(t, k)| (t.to_string(), k)).collect();
+        assert_eq!(tokens, expect);
+    }
+
+    #[test]
+    fn test_validates() {
+        let report = preset().validate();
+        assert!(report.is_clean(), "{report}");
+    }
+}
@@ -0,0 +1,114 @@
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum ReplaceMode {
+    #[default]
+    PerChar,
+    InRun,
+    WholeRun,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::classifier::{Classification, Classifier};
+    use crate::lexer::config::Config;
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::stream::LexerStream;
+    use crate::lexer::token::{classify_base, BaseKind};
+    use std::borrow::Cow;
+    use std::io::{BufReader, Cursor};
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    enum Kind {
+        Tab,
+    }
+
+    struct Upper;
+    impl Classifier for Upper {
+        type Custom = Kind;
+
+        fn classify(&self, c: char) -> Classification<Kind> {
+            match c {
+                's' => (BaseKind::Word, None, Some(Cow::Borrowed("S"))),
+                '\t' => (
+                    BaseKind::Space,
+                    Some(Kind::Tab),
+                    Some(Cow::Borrowed("<TAB>")),
+                ),
+                c => (classify_base(c), None, None),
+            }
+        }
+    }
+
+    fn lex(cfg: &Config<Kind>, input: &str) -> Vec<(String, String)> {
+        LexerInline::new(cfg, &Upper, input)
+            .map(|t| (t.text.to_string(), t.source().to_string()))
+            .collect()
+    }
+
+    fn texts(cfg: &Config<Kind>, input: &str) -> Vec<String> {
+        lex(cfg, input).into_iter().map(|(t, _)| t).collect()
+    }
+
+    #[test]
+    fn test_per_char_is_default() {
+        let cfg = Config::default();
+        assert_eq!(cfg.replace, ReplaceMode::PerChar);
+        assert_eq!(texts(&cfg, "sos\t\t"), ["S", "o", "S", "<TAB>", "<TAB>"]);
+    }
+
+    #[test]
+    fn test_in_run_replaces_inside_groups() {
+        let cfg = Config::default().with_replace(ReplaceMode::InRun);
+        assert_eq!(
+            lex(&cfg, "sos os"),
+            [
+                ("SoS".to_string(), "sos".to_string()),
+                ("oS".to_string(), "os".to_string())
+            ]
+        );
+        assert_eq!(texts(&cfg, "a\t\tb"), ["a", "<TAB><TAB>", "b"]);
+    }
+
+    #[test]
+    fn test_whole_run_collapses() {
+        let cfg = Config::default().with_replace(ReplaceMode::WholeRun);
+        assert_eq!(
+            lex(&cfg, "x\t\t\ty"),
+            [
+                ("x".to_string(), "x".to_string()),
+                ("<TAB>".to_string(), "\t\t\t".to_string()),
+                ("y".to_string(), "y".to_string())
+            ]
+        );
+        assert_eq!(texts(&cfg, "ssa"), ["S", "a"]);
+    }
+
+    #[test]
+    fn test_replaced_text_is_skipped_only_on_request() {
+        let cfg = Config::default().skip_custom([Kind::Tab]);
+        assert_eq!(texts(&cfg, "a\tb"), ["a", "<TAB>", "b"]);
+
+        let cfg = cfg.with_skip_replaced();
+        assert_eq!(texts(&cfg, "a\tb"), ["a", "b"]);
+
+        let cfg = Config::default().with_replace(ReplaceMode::InRun);
+        assert_eq!(texts(&cfg, "a \t b"), ["a", "<TAB>", "b"]);
+    }
+
+    #[test]
+    fn test_stream_matches_inline() {
+        let input = "sos \t\t sas\tsss";
+        for mode in [
+            ReplaceMode::PerChar,
+            ReplaceMode::InRun,
+            ReplaceMode::WholeRun,
+        ] {
+            let cfg = Config::default().with_replace(mode);
+            let reader = BufReader::with_capacity(1, Cursor::new(input));
+            let stream: Vec<_> = LexerStream::new(&cfg, &Upper, reader)
+                .map(|t| (t.text.to_string(), t.source().to_string()))
+                .collect();
+            assert_eq!(stream, lex(&cfg, input));
+        }
+    }
+}
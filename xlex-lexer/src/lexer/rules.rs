@@ -0,0 +1,576 @@
+use super::token::{is_line_break, TokenKind};
+
+use std::hash::Hash;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule<TK: Copy + Eq + Hash> {
+    Literal {
+        text: String,
+        kind: TokenKind<TK>,
+    },
+    LineComment {
+        start: String,
+        standalone: bool,
+        kind: TokenKind<TK>,
+    },
+    BlockComment {
+        start: String,
+        end: String,
+        nested: bool,
+        kind: TokenKind<TK>,
+    },
+    Quoted {
+        open: String,
+        close: String,
+        escape: Option<char>,
+        multiline: bool,
+        kind: TokenKind<TK>,
+    },
+    Char {
+        open: String,
+        close: char,
+        escape: Option<char>,
+        kind: TokenKind<TK>,
+    },
+    Raw {
+        open: String,
+        fence: char,
+        quote: char,
+        kind: TokenKind<TK>,
+    },
+}
+
+pub(crate) enum Found<'r, TK: Copy + Eq + Hash> {
+    None,
+    More,
+    Rule(&'r Rule<TK>),
+}
+
+const MAX_ESCAPE: usize = 10;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub(crate) struct Scanned {
+    at: usize,
+    depth: usize,
+    started: bool,
+}
+
+impl Scanned {
+    #[inline]
+    pub(crate) fn after(self, cut: usize) -> Self {
+        Self {
+            at: self.at - cut,
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Extent {
+    Len(usize),
+    Over(usize),
+    More,
+    Unterminated,
+}
+
+impl<TK: Copy + Eq + Hash> Rule<TK> {
+    #[inline]
+    pub fn start(&self) -> &str {
+        match self {
+            Rule::Literal { text, .. } => text,
+            Rule::LineComment { start, .. } | Rule::BlockComment { start, .. } => start,
+            Rule::Quoted { open, .. } | Rule::Char { open, .. } | Rule::Raw { open, .. } => open,
+        }
+    }
+
+    #[inline]
+    pub fn kind(&self) -> TokenKind<TK> {
+        match *self {
+            Rule::Literal { kind, .. }
+            | Rule::LineComment { kind, .. }
+            | Rule::BlockComment { kind, .. }
+            | Rule::Quoted { kind, .. }
+            | Rule::Char { kind, .. }
+            | Rule::Raw { kind, .. } => kind,
+        }
+    }
+
+    pub(crate) fn bounded(&self, prev: Option<char>, after: &str, more: bool) -> Option<bool> {
+        let start = self.start();
+        let before = match self {
+            Rule::LineComment {
+                standalone: true, ..
+            } => prev.is_none_or(char::is_whitespace),
+            _ => !(is_word(start.chars().next()) && is_word(prev)),
+        };
+        if !before {
+            return Some(false);
+        }
+        let open = match self {
+            Rule::Char { close, escape, .. } => match char_body(after, *close, *escape) {
+                Ok(_) => Some(true),
+                Err(short) => (!short).then_some(false),
+            },
+            Rule::Raw { fence, quote, .. } => after
+                .trim_start_matches(*fence)
+                .chars()
+                .next()
+                .map(|c| c == *quote),
+            _ => {
+                return match is_word(start.chars().next_back()) {
+                    true => after.chars().next().map(|c| !is_word(Some(c))),
+                    false => Some(true),
+                }
+            }
+        };
+        match open {
+            None if !more => Some(false),
+            open => open,
+        }
+    }
+
+    pub(crate) fn extent(
+        &self,
+        rest: &str,
+        more: bool,
+        limit: usize,
+        scanned: &mut Scanned,
+    ) -> Extent {
+        let open = self.start().len();
+        let missing = missing(more);
+        let from = match scanned.started {
+            true => scanned.at,
+            false => open,
+        };
+        match self {
+            Rule::Literal { text, .. } => Extent::Len(text.len()),
+            Rule::LineComment { .. } => {
+                let end = rest[from..].find(is_line_break).map(|i| from + i);
+                if end.unwrap_or(rest.len()) > limit {
+                    let cut = (1..=limit)
+                        .rev()
+                        .find(|&i| rest.is_char_boundary(i))
+                        .unwrap_or_else(|| rest.chars().next().map_or(1, char::len_utf8));
+                    *scanned = Scanned {
+                        at: cut,
+                        depth: 0,
+                        started: true,
+                    };
+                    return Extent::Over(cut);
+                }
+                match end {
+                    Some(end) => Extent::Len(end),
+                    None if more => {
+                        *scanned = Scanned {
+                            at: rest.len(),
+                            depth: 0,
+                            started: true,
+                        };
+                        Extent::More
+                    }
+                    None => Extent::Len(rest.len()),
+                }
+            }
+            Rule::BlockComment {
+                start, end, nested, ..
+            } => {
+                let keep = start.len().max(end.len());
+                let (mut depth, mut i) = match scanned.started {
+                    true => (scanned.depth, from),
+                    false => (1, from),
+                };
+                let mut safe = None;
+                while i < rest.len() {
+                    if i > limit {
+                        let (at, depth) = safe.unwrap_or((i, depth));
+                        return over(scanned, at, depth);
+                    }
+                    if i > 0 {
+                        safe = Some((i, depth));
+                    }
+                    if rest.len() - i >= keep {
+                        *scanned = Scanned {
+                            at: i,
+                            depth,
+                            started: true,
+                        };
+                    }
+                    if rest[i..].starts_with(end.as_str()) {
+                        depth -= 1;
+                        i += end.len();
+                        if depth == 0 {
+                            return match safe {
+                                Some((at, depth)) if i > limit => over(scanned, at, depth),
+                                _ => Extent::Len(i),
+                            };
+                        }
+                    } else if *nested && rest[i..].starts_with(start.as_str()) {
+                        depth += 1;
+                        i += start.len();
+                    } else {
+                        i += rest[i..].chars().next().map_or(1, char::len_utf8);
+                    }
+                }
+                missing
+            }
+            Rule::Quoted {
+                close,
+                escape,
+                multiline,
+                ..
+            } => {
+                let quote = Quote {
+                    close,
+                    escape: *escape,
+                    multiline: *multiline,
+                    depth: 0,
+                };
+                quote.extent(rest, from, more, limit, scanned)
+            }
+            Rule::Char { close, escape, .. } => match char_body(&rest[open..], *close, *escape) {
+                Ok(len) => Extent::Len(open + len),
+                Err(_) => Extent::Unterminated,
+            },
+            Rule::Raw { fence, quote, .. } => {
+                let (depth, from) = match scanned.started {
+                    true => (scanned.depth, from),
+                    false => {
+                        let fences =
+                            rest[open..].len() - rest[open..].trim_start_matches(*fence).len();
+                        let depth = fences / fence.len_utf8();
+                        (depth, open + fences + quote.len_utf8())
+                    }
+                };
+                let close: String = std::iter::once(*quote)
+                    .chain(std::iter::repeat_n(*fence, depth))
+                    .collect();
+                let quote = Quote {
+                    close: &close,
+                    escape: None,
+                    multiline: true,
+                    depth,
+                };
+                quote.extent(rest, from, more, limit, scanned)
+            }
+        }
+    }
+}
+
+struct Quote<'q> {
+    close: &'q str,
+    escape: Option<char>,
+    multiline: bool,
+    depth: usize,
+}
+
+impl Quote<'_> {
+    fn extent(
+        &self,
+        rest: &str,
+        from: usize,
+        more: bool,
+        limit: usize,
+        scanned: &mut Scanned,
+    ) -> Extent {
+        let close = self.close;
+        let doubled = self.escape.is_some_and(|e| close.starts_with(e));
+        let keep = 2 * close.len() + 8;
+        let mut safe = None;
+        let mut chars = rest[from..].char_indices();
+        while let Some((i, ch)) = chars.next() {
+            let at = from + i;
+            if at > limit {
+                return over(scanned, safe.unwrap_or(at), self.depth);
+            }
+            if at > 0 {
+                safe = Some(at);
+            }
+            if rest.len() - at >= keep {
+                *scanned = Scanned {
+                    at,
+                    depth: self.depth,
+                    started: true,
+                };
+            }
+            if rest[at..].starts_with(close) {
+                let after = &rest[at + close.len()..];
+                match doubled {
+                    true if after.starts_with(close) => {
+                        chars.nth(2 * close.chars().count() - 2);
+                        continue;
+                    }
+                    true if after.is_empty() && more => return Extent::More,
+                    _ if at > 0 && at + close.len() > limit => {
+                        return over(scanned, at, self.depth);
+                    }
+                    _ => return Extent::Len(at + close.len()),
+                }
+            }
+            if Some(ch) == self.escape && chars.next().is_none() {
+                return missing(more);
+            }
+            if !self.multiline && is_line_break(ch) {
+                return Extent::Unterminated;
+            }
+        }
+        missing(more)
+    }
+}
+
+#[inline]
+fn missing(more: bool) -> Extent {
+    match more {
+        true => Extent::More,
+        false => Extent::Unterminated,
+    }
+}
+
+fn char_body(after: &str, close: char, escape: Option<char>) -> Result<usize, bool> {
+    let mut chars = after.char_indices();
+    let Some((_, c)) = chars.next() else {
+        return Err(true);
+    };
+    if c == close || is_line_break(c) {
+        return Err(false);
+    }
+    let span = match Some(c) == escape {
+        true if chars.next().is_none() => return Err(true),
+        true => MAX_ESCAPE,
+        false => 1,
+    };
+    let mut taken = 0;
+    for (i, c) in chars.take(span) {
+        if c == close {
+            return Ok(i + c.len_utf8());
+        }
+        if is_line_break(c) || Some(c) == escape {
+            return Err(false);
+        }
+        taken += 1;
+    }
+    Err(taken < span)
+}
+
+#[inline]
+fn over(scanned: &mut Scanned, at: usize, depth: usize) -> Extent {
+    *scanned = Scanned {
+        at,
+        depth,
+        started: true,
+    };
+    Extent::Over(at)
+}
+
+#[inline]
+fn is_word(c: Option<char>) -> bool {
+    c.is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::classifier::{Classification, Classifier};
+    use crate::lexer::config::Config;
+    use crate::lexer::error::LexErrorKind;
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::limits::{Limits, Overflow};
+    use crate::lexer::stream::LexerStream;
+    use crate::lexer::token::{classify_base, BaseKind};
+    use std::io::{BufReader, Cursor};
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    enum Kind {
+        Op,
+        Comment,
+        Str,
+    }
+
+    struct Base;
+    impl Classifier for Base {
+        type Custom = Kind;
+
+        fn classify(&self, c: char) -> Classification<Kind> {
+            (classify_base(c), None, None)
+        }
+    }
+
+    fn config() -> Config<Kind> {
+        let op = TokenKind::new(BaseKind::Symbol, Some(Kind::Op));
+        let comment = TokenKind::new(BaseKind::Symbol, Some(Kind::Comment));
+        let string = TokenKind::new(BaseKind::Word, Some(Kind::Str));
+        Config::default()
+            .literal("=", op)
+            .literal("==", op)
+            .literal("=>", op)
+            .line_comment("//", comment)
+            .block_comment("/*", "*/", true, comment)
+            .quoted("\"", "\"", Some('\\'), false, string)
+            .quoted("r\"", "\"", None, true, string)
+    }
+
+    fn texts(cfg: &Config<Kind>, input: &str) -> Vec<String> {
+        LexerInline::new(cfg, &Base, input)
+            .map(|t| t.text.into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_longest_literal_wins() {
+        let cfg = config();
+        assert_eq!(
+            texts(&cfg, "a==b=>c=d"),
+            ["a", "==", "b", "=>", "c", "=", "d"]
+        );
+    }
+
+    #[test]
+    fn test_comments_and_strings() {
+        let cfg = config();
+        assert_eq!(
+            texts(&cfg, "x // rest\ny /* a /* b */ c */ \"q\\\"t\" r\"1\n2\""),
+            [
+                "x",
+                "// rest",
+                "y",
+                "/* a /* b */ c */",
+                "\"q\\\"t\"",
+                "r\"1\n2\""
+            ]
+        );
+
+        let cfg = config().skip_custom([Kind::Comment]);
+        assert_eq!(texts(&cfg, "a /* b */ c // d"), ["a", "c"]);
+    }
+
+    #[test]
+    fn test_rules_stop_grouped_runs() {
+        let cfg = config().with_grouped_symbols();
+        assert_eq!(
+            texts(&cfg, "a!/* c */b =// x"),
+            ["a", "!", "/* c */", "b", "=", "// x"]
+        );
+    }
+
+    #[test]
+    fn test_word_literals_need_boundaries() {
+        let op = TokenKind::new(BaseKind::Word, Some(Kind::Op));
+        let comment = TokenKind::new(BaseKind::Symbol, Some(Kind::Comment));
+        let cfg = Config::default()
+            .literal("in", op)
+            .standalone_comment("#", comment);
+        let input = "int x in xin a#b # c";
+        let expect = ["int", "x", "in", "xin", "a", "#", "b", "# c"];
+        assert_eq!(texts(&cfg, input), expect);
+
+        let reader = BufReader::with_capacity(1, Cursor::new(input));
+        let stream: Vec<_> = LexerStream::new(&cfg, &Base, reader)
+            .map(|t| t.text.into_owned())
+            .collect();
+        assert_eq!(stream, expect);
+    }
+
+    #[test]
+    fn test_unterminated() {
+        let cfg = config();
+        for input in ["a \"open", "a \"line\nbreak\"", "/* a /* b */"] {
+            let mut lexer = LexerInline::new(&cfg, &Base, input);
+            lexer.by_ref().for_each(drop);
+            assert_eq!(lexer.error().unwrap().kind, LexErrorKind::Unterminated);
+        }
+    }
+
+    #[test]
+    fn test_doubled_close_escapes() {
+        let string = TokenKind::new(BaseKind::Word, Some(Kind::Str));
+        let cfg = Config::default().quoted("'", "'", Some('\''), false, string);
+        assert_eq!(texts(&cfg, "'it''s' '''' ''"), ["'it''s'", "''''", "''"]);
+
+        let reader = BufReader::with_capacity(1, Cursor::new("'a''b' 'c'"));
+        let stream: Vec<_> = LexerStream::new(&cfg, &Base, reader)
+            .map(|t| t.text.into_owned())
+            .collect();
+        assert_eq!(stream, ["'a''b'", "'c'"]);
+    }
+
+    #[test]
+    fn test_stream_matches_inline() {
+        let input = concat!(
+            "a==b /* x\n /* nested */ 0123456789abcdef */ ",
+            "\"s\\\"q 0123456789abcdef \\\\\" // end 0123456789\n",
+            "=> r\"m\nl 0123456789abcdef\" ="
+        );
+        let cfg = config();
+        let inline: Vec<_> = LexerInline::new(&cfg, &Base, input)
+            .map(|t| (t.text.into_owned(), t.kind, t.start))
+            .collect();
+        assert_eq!(inline.len(), 9);
+        for capacity in 1..=8 {
+            let reader = BufReader::with_capacity(capacity, Cursor::new(input));
+            let stream: Vec<_> = LexerStream::new(&cfg, &Base, reader)
+                .map(|t| (t.text.into_owned(), t.kind, t.start))
+                .collect();
+            assert_eq!(stream, inline);
+        }
+    }
+
+    #[test]
+    fn test_char_and_raw_rules() {
+        let string = TokenKind::new(BaseKind::Word, Some(Kind::Str));
+        let cfg = Config::default()
+            .char_literal("'", '\'', Some('\\'), string)
+            .raw_quoted("R", '#', '"', string);
+        let input = "'\"' '\\'' '\\u{1F600}' R\"a\" R##\"b\"#\"## R#x 'ab 'a";
+        let expect = [
+            "'\"'",
+            "'\\''",
+            "'\\u{1F600}'",
+            "R\"a\"",
+            "R##\"b\"#\"##",
+            "R",
+            "#",
+            "x",
+            "'",
+            "ab",
+            "'",
+            "a",
+        ];
+        assert_eq!(texts(&cfg, input), expect);
+        for capacity in 1..=5 {
+            let reader = BufReader::with_capacity(capacity, Cursor::new(input));
+            let stream: Vec<_> = LexerStream::new(&cfg, &Base, reader)
+                .map(|t| t.text.into_owned())
+                .collect();
+            assert_eq!(stream, expect);
+        }
+
+        let cfg = cfg.with_limits(Limits::default().max_token_len(4, Overflow::Split));
+        assert_eq!(texts(&cfg, "R##\"a\"#bc\"##"), ["R##\"", "a\"#b", "c\"##"]);
+    }
+
+    #[test]
+    fn test_literals_ignore_case() {
+        let cfg = Config::default()
+            .literal("AND", TokenKind::new(BaseKind::Word, Some(Kind::Op)))
+            .keywords([("or", Kind::Op), ("1", Kind::Str), ("+", Kind::Str)])
+            .with_case_insensitive_keywords();
+        let expect = [
+            ("and", Some(Kind::Op)),
+            ("Or", Some(Kind::Op)),
+            ("1", None),
+            ("+", None),
+            ("AnD", Some(Kind::Op)),
+        ];
+        let input = "and Or 1 + AnD";
+        let inline: Vec<_> = LexerInline::new(&cfg, &Base, input)
+            .filter(|t| t.kind.base != BaseKind::Space)
+            .map(|t| (t.text.into_owned(), t.kind.custom))
+            .collect();
+        let stream: Vec<_> = LexerStream::new(&cfg, &Base, input.as_bytes())
+            .filter(|t| t.kind.base != BaseKind::Space)
+            .map(|t| (t.text.into_owned(), t.kind.custom))
+            .collect();
+        let expect: Vec<_> = expect.iter().map(|&(t, k)| (t.to_string(), k)).collect();
+        assert_eq!(inline, expect);
+        assert_eq!(stream, expect);
+    }
+}
@@ -0,0 +1,513 @@
+use super::classifier::Classification;
+use super::compiled::CompiledConfig;
+use super::context::ContextClassifier;
+use super::error::{LexError, LexErrorKind};
+use super::grouping::Grouping;
+use super::limits::Overflow;
+use super::normalize::is_combining_mark;
+use super::offside::{Measure, OffsideState, Synthetic};
+use super::replace::ReplaceMode;
+use super::rules::{Extent, Found, Rule, Scanned};
+use super::token::{is_line_break, BaseKind, Newlines, TokenKind};
+
+use std::{borrow::Cow, hash::Hash};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub(crate) struct State {
+    pub(crate) pos: usize,
+    pub(crate) tokens: usize,
+    pub(crate) prev: Option<char>,
+    pub(crate) offside: OffsideState,
+    pub(crate) split: Option<(usize, Scanned)>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Tail {
+    More,
+    Break,
+    Eof,
+}
+
+pub(crate) struct Raw<TK: Copy + Eq + Hash> {
+    pub(crate) kind: TokenKind<TK>,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) repl: Option<Cow<'static, str>>,
+}
+
+pub(crate) enum Step<TK: Copy + Eq + Hash> {
+    Token(Raw<TK>),
+    Skipped(Raw<TK>),
+    More,
+    Done,
+    Error(LexError),
+}
+
+pub(crate) struct Scanner<'c, TK, CL>
+where
+    TK: Copy + Eq + Hash,
+    CL: ContextClassifier<Custom = TK> + ?Sized,
+{
+    pub(crate) config: &'c CompiledConfig<'c, TK>,
+    pub(crate) classifier: &'c CL,
+}
+
+pub(crate) enum Resume<TK: Copy + Eq + Hash, S> {
+    Extent(Scanned),
+    Run(Partial<TK, S>),
+}
+
+pub(crate) struct Partial<TK: Copy + Eq + Hash, S> {
+    kind: TokenKind<TK>,
+    skip: bool,
+    repl: Option<Cow<'static, str>>,
+    saved: S,
+    progress: Progress,
+}
+
+struct Progress {
+    first: char,
+    len: usize,
+    prev: char,
+    count: usize,
+    text: Option<String>,
+}
+
+struct Run {
+    len: usize,
+    open: bool,
+    full: bool,
+}
+
+impl<'c, TK, CL> Scanner<'c, TK, CL>
+where
+    TK: Copy + Eq + Hash,
+    CL: ContextClassifier<Custom = TK> + ?Sized,
+{
+    #[inline]
+    pub(crate) fn new(config: &'c CompiledConfig<'c, TK>, classifier: &'c CL) -> Self {
+        Self { config, classifier }
+    }
+
+    pub(crate) fn step(
+        &self,
+        state: &mut State,
+        context: &mut CL::State,
+        resume: &mut Option<Resume<TK, CL::Snapshot>>,
+        input: &str,
+        base: usize,
+        tail: Tail,
+    ) -> Step<TK> {
+        let step = self.advance(state, context, resume, input, base, tail);
+        if let (Step::Token(raw), Some(max)) = (&step, self.config.limits.max_tokens) {
+            if state.tokens == max {
+                return Step::Error(LexError::new(LexErrorKind::TooManyTokens, raw.start));
+            }
+            state.tokens += 1;
+        }
+        step
+    }
+
+    fn advance(
+        &self,
+        state: &mut State,
+        context: &mut CL::State,
+        resume: &mut Option<Resume<TK, CL::Snapshot>>,
+        input: &str,
+        base: usize,
+        tail: Tail,
+    ) -> Step<TK> {
+        loop {
+            let rel = state.pos - base;
+            if let Some(rules) = &self.config.offside {
+                if let Some((synthetic, at)) = state.offside.pending(state.pos) {
+                    return Step::Token(Raw::synthetic(synthetic, at));
+                }
+                if rel < input.len() && state.offside.line_start && state.offside.brackets == 0 {
+                    let more = tail == Tail::More;
+                    let comment = |rest: &str| {
+                        let ch = rest.chars().next()?;
+                        match self.config.rule(rest, ch, None, more) {
+                            Found::More => None,
+                            Found::Rule(rule) => Some(matches!(rule, Rule::LineComment { .. })),
+                            Found::None => Some(false),
+                        }
+                    };
+                    match state
+                        .offside
+                        .measure(rules, &input[rel..], state.pos, more, comment)
+                    {
+                        Ok(Measure::More) => return Step::More,
+                        Ok(Measure::Done) => continue,
+                        Err(e) => return Step::Error(e),
+                    }
+                }
+            }
+            if rel == input.len() {
+                return match tail {
+                    Tail::More => Step::More,
+                    Tail::Break => Step::Done,
+                    Tail::Eof => match self.config.offside {
+                        Some(_) => match state.offside.finish() {
+                            Some(synthetic) => Step::Token(Raw::synthetic(synthetic, state.pos)),
+                            None => Step::Done,
+                        },
+                        None => Step::Done,
+                    },
+                };
+            }
+
+            let rest = &input[rel..];
+            let ch = rest.chars().next().unwrap();
+            let start = state.pos;
+            let offside = self.config.offside.is_some();
+            let line_break = offside && is_line_break(ch);
+
+            let split = state.split.is_some();
+            if line_break && !split && state.offside.brackets == 0 && state.offside.line_content {
+                state.offside.line_content = false;
+                return Step::Token(Raw::synthetic(Synthetic::Newline, start));
+            }
+
+            let more = tail == Tail::More;
+            let (kind, skip, mut repl, saved, mut progress) = match resume.take() {
+                Some(Resume::Run(partial)) => (
+                    partial.kind,
+                    partial.skip,
+                    partial.repl,
+                    partial.saved,
+                    Some(partial.progress),
+                ),
+                resumed => {
+                    let mut scanned = match (resumed, state.split) {
+                        (Some(Resume::Extent(scanned)), _) => scanned,
+                        (_, Some((_, scanned))) => scanned,
+                        _ => Scanned::default(),
+                    };
+                    let rule = match state.split {
+                        Some((index, _)) => Some(&self.config.rules[index]),
+                        None => match self.config.rule(rest, ch, state.prev, more) {
+                            Found::None => None,
+                            Found::More => return Step::More,
+                            Found::Rule(rule) => Some(rule),
+                        },
+                    };
+                    if let Some(rule) = rule {
+                        let kind = rule.kind();
+                        let skip = self.config.should_skip(kind.base, kind.custom);
+                        let max = self.config.limits.max_token_len.unwrap_or(usize::MAX);
+                        let error = !skip && self.config.limits.overflow == Overflow::Error;
+                        let len = match rule.extent(rest, more, max, &mut scanned) {
+                            Extent::Len(len) => {
+                                state.split = None;
+                                len
+                            }
+                            Extent::Over(_) if error => {
+                                return Step::Error(LexError::new(
+                                    LexErrorKind::TokenTooLong,
+                                    start,
+                                ));
+                            }
+                            Extent::Over(cut) => {
+                                let index = match state.split {
+                                    Some((index, _)) => index,
+                                    None => self
+                                        .config
+                                        .rules
+                                        .iter()
+                                        .position(|r| std::ptr::eq(r, rule))
+                                        .unwrap(),
+                                };
+                                state.split = Some((index, scanned.after(cut)));
+                                cut
+                            }
+                            Extent::More => {
+                                *resume = Some(Resume::Extent(scanned));
+                                return Step::More;
+                            }
+                            Extent::Unterminated => {
+                                return Step::Error(LexError::new(
+                                    LexErrorKind::Unterminated,
+                                    start,
+                                ));
+                            }
+                        };
+                        if len > max && error {
+                            return Step::Error(LexError::new(LexErrorKind::TokenTooLong, start));
+                        }
+
+                        let end = start + len;
+                        state.pos = end;
+                        state.prev = rest[..len].chars().next_back();
+                        if let (Some(rules), false) = (&self.config.offside, skip) {
+                            match rule {
+                                Rule::Literal { text, .. } => state.offside.track(rules, text),
+                                Rule::LineComment { .. } => {}
+                                _ => state.offside.line_content = true,
+                            }
+                        }
+
+                        let raw = Raw {
+                            kind,
+                            start,
+                            end,
+                            repl: None,
+                        };
+                        match (skip, self.config.trivia) {
+                            (false, _) => return Step::Token(raw),
+                            (true, Some(_)) => return Step::Skipped(raw),
+                            (true, None) => continue,
+                        }
+                    }
+
+                    let need = self.classifier.lookahead();
+                    if more && short(&rest[ch.len_utf8()..], need) {
+                        return Step::More;
+                    }
+                    let saved = self.classifier.snapshot(context);
+                    let (bk, ck, repl) =
+                        self.classify(context, state.prev, ch, &rest[ch.len_utf8()..]);
+                    if bk == BaseKind::Error && self.config.strict {
+                        self.classifier.restore(context, saved);
+                        return Step::Error(LexError::new(LexErrorKind::InvalidChar(ch), start));
+                    }
+                    let skip = self.config.skips(bk, ck, repl.is_some());
+                    (TokenKind::new(bk, ck), skip, repl, saved, None)
+                }
+            };
+            let bk = kind.base;
+
+            let single = line_break
+                || (bk == BaseKind::Newline && self.config.newlines == Some(Newlines::PerBreak));
+            let run = if let Some(progress) = &mut progress {
+                let flags = self.flags(kind, skip, offside, more);
+                self.scan(context, rest, kind, &repl, progress, flags)
+            } else if single && is_line_break(ch) {
+                match rest.as_bytes() {
+                    [b'\r', b'\n', ..] => Run {
+                        len: 2,
+                        open: false,
+                        full: false,
+                    },
+                    [b'\r'] => Run {
+                        len: 1,
+                        open: true,
+                        full: false,
+                    },
+                    _ => Run {
+                        len: ch.len_utf8(),
+                        open: false,
+                        full: false,
+                    },
+                }
+            } else if repl.is_some() && self.config.replace == ReplaceMode::PerChar {
+                Run {
+                    len: ch.len_utf8(),
+                    open: false,
+                    full: false,
+                }
+            } else {
+                let flags = self.flags(kind, skip, offside, more);
+                if flags.grouping != Grouping::Never || flags.marks {
+                    let text = match (self.config.replace, &repl) {
+                        (ReplaceMode::InRun, Some(r)) => Some(r.to_string()),
+                        _ => None,
+                    };
+                    let progress = progress.insert(Progress {
+                        first: ch,
+                        len: ch.len_utf8(),
+                        prev: ch,
+                        count: 1,
+                        text,
+                    });
+                    self.scan(context, rest, kind, &repl, progress, flags)
+                } else {
+                    Run {
+                        len: ch.len_utf8(),
+                        open: false,
+                        full: false,
+                    }
+                }
+            };
+            if run.open && more {
+                match progress {
+                    Some(progress) => {
+                        *resume = Some(Resume::Run(Partial {
+                            kind,
+                            skip,
+                            repl,
+                            saved,
+                            progress,
+                        }));
+                    }
+                    None => self.classifier.restore(context, saved),
+                }
+                return Step::More;
+            }
+            if run.full && !skip && self.config.limits.overflow == Overflow::Error {
+                self.classifier.restore(context, saved);
+                return Step::Error(LexError::new(LexErrorKind::TokenTooLong, start));
+            }
+            if let Some(text) = progress.and_then(|p| p.text) {
+                repl = Some(Cow::Owned(text));
+            }
+
+            let end = start + run.len;
+            state.pos = end;
+            state.prev = rest[..run.len].chars().next_back();
+            if let Some(rules) = &self.config.offside {
+                if line_break {
+                    if state.offside.brackets == 0 {
+                        state.offside.line_start = true;
+                    }
+                } else if bk != BaseKind::Space && bk != BaseKind::Newline {
+                    state.offside.track(rules, &rest[..run.len]);
+                }
+            }
+
+            let raw = Raw {
+                kind,
+                start,
+                end,
+                repl,
+            };
+            match (skip, self.config.trivia) {
+                (false, _) => return Step::Token(raw),
+                (true, Some(_)) => return Step::Skipped(raw),
+                (true, None) => continue,
+            }
+        }
+    }
+
+    #[inline]
+    fn flags(&self, kind: TokenKind<TK>, skip: bool, offside: bool, more: bool) -> Flags {
+        Flags {
+            skip,
+            grouping: match skip {
+                true => Grouping::Always,
+                false => self.config.grouping(kind.base, kind.custom),
+            },
+            marks: self.config.normalization.is_some(),
+            offside,
+            more,
+        }
+    }
+    #[inline]
+    pub(crate) fn classify(
+        &self,
+        context: &mut CL::State,
+        prev: Option<char>,
+        ch: char,
+        next: &str,
+    ) -> Classification<TK> {
+        let (bk, ck, repl) = self.classifier.classify_at(context, prev, ch, next);
+        if bk != BaseKind::Error && self.config.is_invalid(ch) {
+            return (BaseKind::Error, ck, None);
+        }
+        if bk == BaseKind::Space && self.config.newlines.is_some() && is_line_break(ch) {
+            return (BaseKind::Newline, ck, repl);
+        }
+        (bk, ck, repl)
+    }
+
+    fn scan(
+        &self,
+        context: &mut CL::State,
+        rest: &str,
+        kind: TokenKind<TK>,
+        repl: &Option<Cow<'static, str>>,
+        progress: &mut Progress,
+        flags: Flags,
+    ) -> Run {
+        let need = self.classifier.lookahead();
+        let max = self.config.limits.max_token_len.unwrap_or(usize::MAX);
+        let mode = self.config.replace;
+        let (base, custom) = (kind.base, kind.custom);
+        let (open, full) = loop {
+            let len = progress.len;
+            let Some(ch) = rest[len..].chars().next() else {
+                break (true, false);
+            };
+            if flags.offside && is_line_break(ch) {
+                break (false, false);
+            }
+            match self
+                .config
+                .rule(&rest[len..], ch, Some(progress.prev), flags.more)
+            {
+                Found::None => {}
+                Found::More => break (true, false),
+                Found::Rule(_) => break (false, false),
+            }
+            let next = &rest[len + ch.len_utf8()..];
+            if flags.more && short(next, need) {
+                break (true, false);
+            }
+            let saved = self.classifier.snapshot(context);
+            let (bk, ck, r) = self.classify(context, Some(progress.prev), ch, next);
+            let kind = (bk == base && ck == custom) || self.config.merges(base, bk);
+            let same = kind && flags.grouping.extends(progress.count, progress.first, ch);
+            let absorb = flags.marks && is_combining_mark(ch);
+            let fits = match mode {
+                ReplaceMode::PerChar => r.is_none(),
+                ReplaceMode::InRun => !same || self.config.skips(bk, ck, r.is_some()) == flags.skip,
+                ReplaceMode::WholeRun => r == *repl,
+            };
+            if !fits || (!same && !absorb) {
+                self.classifier.restore(context, saved);
+                break (false, false);
+            }
+            if len + ch.len_utf8() > max {
+                self.classifier.restore(context, saved);
+                break (false, true);
+            }
+            if mode == ReplaceMode::InRun {
+                match (&mut progress.text, r) {
+                    (Some(t), Some(r)) => t.push_str(&r),
+                    (Some(t), None) => t.push(ch),
+                    (None, Some(r)) => progress.text = Some(format!("{}{}", &rest[..len], r)),
+                    (None, None) => {}
+                }
+            }
+            progress.len += ch.len_utf8();
+            progress.prev = ch;
+            progress.count += 1;
+        };
+        Run {
+            len: progress.len,
+            open,
+            full,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Flags {
+    skip: bool,
+    grouping: Grouping,
+    marks: bool,
+    offside: bool,
+    more: bool,
+}
+
+#[inline]
+fn short(next: &str, need: usize) -> bool {
+    need > 0 && next.chars().take(need).count() < need
+}
+
+impl<TK: Copy + Eq + Hash> Raw<TK> {
+    #[inline]
+    fn synthetic(synthetic: Synthetic, at: usize) -> Self {
+        let base = match synthetic {
+            Synthetic::Indent => BaseKind::Indent,
+            Synthetic::Dedent => BaseKind::Dedent,
+            Synthetic::Newline => BaseKind::Newline,
+        };
+        Raw {
+            kind: TokenKind::new(base, None),
+            start: at,
+            end: at,
+            repl: None,
+        }
+    }
+}
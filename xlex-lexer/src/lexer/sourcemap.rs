@@ -0,0 +1,142 @@
+use super::token::Token;
+
+use std::{borrow::Borrow, hash::Hash, ops::Range};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Chunk {
+    output_start: usize,
+    output_end: usize,
+    source_start: usize,
+    source_end: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    chunks: Vec<Chunk>,
+}
+
+impl SourceMap {
+    pub fn build<'a, TK, IT>(input: &str, tokens: IT) -> (String, SourceMap)
+    where
+        TK: Copy + Eq + Hash,
+        IT: IntoIterator,
+        IT::Item: Borrow<Token<'a, TK>>,
+    {
+        let mut out = String::with_capacity(input.len());
+        let mut map = SourceMap::default();
+        let mut pos = 0;
+        for token in tokens {
+            let token = token.borrow();
+            if token.start < pos {
+                continue;
+            }
+            out.push_str(&input[pos..token.start]);
+            let at = out.len();
+            out.push_str(&token.text);
+            if token.text.as_ref() != &input[token.start..token.end] {
+                map.chunks.push(Chunk {
+                    output_start: at,
+                    output_end: out.len(),
+                    source_start: token.start,
+                    source_end: token.end,
+                });
+            }
+            pos = token.end;
+        }
+        out.push_str(&input[pos..]);
+        (out, map)
+    }
+
+    pub fn original(&self, offset: usize) -> usize {
+        match self.chunk_before(offset, false) {
+            None => offset,
+            Some(c) if offset < c.output_end => c.source_start,
+            Some(c) => c.source_end + (offset - c.output_end),
+        }
+    }
+
+    pub fn original_span(&self, span: Range<usize>) -> Range<usize> {
+        let start = self.original(span.start);
+        let end = match self.chunk_before(span.end, true) {
+            None => span.end,
+            Some(c) if span.end <= c.output_end => c.source_end,
+            Some(c) => c.source_end + (span.end - c.output_end),
+        };
+        start..end.max(start)
+    }
+
+    #[inline]
+    fn chunk_before(&self, offset: usize, exclusive: bool) -> Option<Chunk> {
+        let i = self.chunks.partition_point(|c| match exclusive {
+            true => c.output_start < offset,
+            false => c.output_start <= offset,
+        });
+        self.chunks.get(i.checked_sub(1)?).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::classifier::Classifier;
+    use crate::lexer::config::Config;
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::normalize::Normalization;
+    use crate::lexer::stream::LexerStream;
+    use crate::lexer::token::{classify_base, BaseKind};
+    use std::borrow::Cow;
+    use std::io::Cursor;
+
+    struct Tabs;
+    impl Classifier for Tabs {
+        type Custom = ();
+
+        fn classify(&self, c: char) -> (BaseKind, Option<()>, Option<Cow<'static, str>>) {
+            match c {
+                '\t' => (BaseKind::Space, None, Some(Cow::Borrowed("<TAB>"))),
+                _ => (classify_base(c), None, None),
+            }
+        }
+    }
+
+    #[test]
+    fn test_tokens_keep_original_text() {
+        let cfg = Config::default();
+        let tokens: Vec<_> = LexerInline::new(&cfg, &Tabs, "a\tb").collect();
+        assert_eq!(tokens[1].text, "<TAB>");
+        assert_eq!(tokens[1].source(), "\t");
+        assert_eq!(tokens[0].original, None);
+        assert_eq!(tokens[0].source(), "a");
+
+        let stream: Vec<_> = LexerStream::new(&cfg, &Tabs, Cursor::new("a\tb")).collect();
+        assert_eq!(stream[1].original.as_deref(), Some("\t"));
+    }
+
+    #[test]
+    fn test_normalized_tokens_keep_original_text() {
+        let cfg = Config::default()
+            .with_normalization(Normalization::Nfc)
+            .with_case_folding();
+        let tokens: Vec<_> = LexerInline::new(&cfg, &Tabs, "Cafe\u{301} x").collect();
+        assert_eq!(tokens[0].text, "café");
+        assert_eq!(tokens[0].source(), "Cafe\u{301}");
+        assert_eq!(tokens[1].original, None);
+    }
+
+    #[test]
+    fn test_source_map_points_back_to_input() {
+        let input = "x\t\ty = 1";
+        let cfg = Config::default();
+        let tokens = LexerInline::new(&cfg, &Tabs, input);
+        let (out, map) = SourceMap::build(input, tokens);
+
+        assert_eq!(out, "x<TAB><TAB>y = 1");
+        let y = out.find('y').unwrap();
+        assert_eq!(map.original(y), 3);
+        assert_eq!(map.original(0), 0);
+        assert_eq!(map.original(3), 1);
+        assert_eq!(map.original(8), 2);
+        assert_eq!(map.original_span(1..11), 1..3);
+        assert_eq!(map.original_span(y..out.len()), 3..input.len());
+    }
+}
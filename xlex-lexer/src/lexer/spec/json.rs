@@ -0,0 +1,267 @@
+use super::node::{Lines, Node, Value};
+use super::SpecError;
+
+const MAX_DEPTH: usize = 128;
+
+pub(crate) fn parse(src: &str) -> Result<Node, SpecError> {
+    let mut parser = Parser {
+        src,
+        lines: Lines::new(src),
+        pos: 0,
+        depth: 0,
+    };
+    let node = parser.value()?;
+    parser.space();
+    match parser.pos < src.len() {
+        true => Err(parser.error("trailing characters after document")),
+        false => Ok(node),
+    }
+}
+
+struct Parser<'s> {
+    src: &'s str,
+    lines: Lines,
+    pos: usize,
+    depth: usize,
+}
+
+impl<'s> Parser<'s> {
+    #[inline]
+    fn error(&self, message: &str) -> SpecError {
+        SpecError::new("", self.lines.line(self.pos), message)
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn space(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_ascii_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn eat(&mut self, c: char) -> Result<(), SpecError> {
+        self.space();
+        match self.peek() {
+            Some(p) if p == c => {
+                self.pos += 1;
+                Ok(())
+            }
+            _ => Err(self.error(&format!("expected `{c}`"))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Node, SpecError> {
+        self.space();
+        let line = self.lines.line(self.pos);
+        let value = match self.peek() {
+            Some('{' | '[') if self.depth == MAX_DEPTH => {
+                return Err(self.error("nesting is too deep"));
+            }
+            Some('{') => {
+                self.depth += 1;
+                let value = self.object()?;
+                self.depth -= 1;
+                value
+            }
+            Some('[') => {
+                self.depth += 1;
+                let value = self.array()?;
+                self.depth -= 1;
+                value
+            }
+            Some('"') => Value::Str(self.string()?),
+            Some('-' | '0'..='9') => self.number()?,
+            Some(_) if self.keyword("true") => Value::Bool(true),
+            Some(_) if self.keyword("false") => Value::Bool(false),
+            Some(_) if self.keyword("null") => return Err(self.error("null is not supported")),
+            Some(_) => return Err(self.error("expected a value")),
+            None => return Err(self.error("unexpected end of input")),
+        };
+        Ok(Node::new(value, line))
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        let found = self.src[self.pos..].starts_with(word);
+        if found {
+            self.pos += word.len();
+        }
+        found
+    }
+
+    fn object(&mut self) -> Result<Value, SpecError> {
+        self.eat('{')?;
+        let mut entries = Vec::new();
+        self.space();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Value::Table(entries));
+        }
+        loop {
+            self.space();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a key"));
+            }
+            let key = self.string()?;
+            self.eat(':')?;
+            entries.push((key, self.value()?));
+            self.space();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(Value::Table(entries));
+                }
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, SpecError> {
+        self.eat('[')?;
+        let mut items = Vec::new();
+        self.space();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.space();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, SpecError> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(out),
+                '\\' => out.push(self.escape()?),
+                c if (c as u32) < 0x20 => return Err(self.error("control character in string")),
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, SpecError> {
+        let c = self
+            .peek()
+            .ok_or_else(|| self.error("unterminated string"))?;
+        self.pos += c.len_utf8();
+        Ok(match c {
+            '"' => '"',
+            '\\' => '\\',
+            '/' => '/',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'u' => {
+                let high = self.hex()?;
+                let code = match high {
+                    0xD800..=0xDBFF if self.keyword("\\u") => match self.hex()? {
+                        low @ 0xDC00..=0xDFFF => 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00),
+                        _ => return Err(self.error("invalid surrogate pair")),
+                    },
+                    code => code,
+                };
+                char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))?
+            }
+            _ => return Err(self.error("invalid escape")),
+        })
+    }
+
+    fn hex(&mut self) -> Result<u32, SpecError> {
+        let digits = self.src.get(self.pos..self.pos + 4);
+        let code = digits
+            .filter(|d| d.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|d| u32::from_str_radix(d, 16).ok());
+        let code = code.ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Value, SpecError> {
+        let start = self.pos;
+        let len = self.src[start..]
+            .find(|c: char| !matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9'))
+            .unwrap_or(self.src.len() - start);
+        self.pos += len;
+        self.src[start..self.pos]
+            .parse()
+            .map(Value::Int)
+            .map_err(|_| self.error("expected an integer"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_values() {
+        let node = parse("{\"a\": [1, -2, true],\n \"b\": {\"c\": \"x\\n\\u00e9\\ud83d\\ude00\"}}")
+            .unwrap();
+        let table = node.table("").unwrap();
+        assert_eq!(table[0].0, "a");
+        assert_eq!(
+            table[0].1.value,
+            Value::Array(vec![
+                Node::new(Value::Int(1), 1),
+                Node::new(Value::Int(-2), 1),
+                Node::new(Value::Bool(true), 1),
+            ])
+        );
+        let inner = table[1].1.table("b").unwrap();
+        assert_eq!(inner[0].1.str("c").unwrap(), "x\né😀");
+        assert_eq!(table[1].1.line, 2);
+    }
+
+    #[test]
+    fn test_errors_report_line() {
+        let err = parse("{\n\"a\": 1,\n\"b\" 2\n}").unwrap_err();
+        assert_eq!(err.line, 3);
+        assert_eq!(parse("[1.5]").unwrap_err().message, "expected an integer");
+        assert_eq!(parse("{} x").unwrap_err().line, 1);
+    }
+
+    #[test]
+    fn test_rejects_deep_nesting() {
+        let src = format!("{{\"skip\": {}", "[".repeat(200_000));
+        assert_eq!(parse(&src).unwrap_err().message, "nesting is too deep");
+        let ok = format!("{}{}", "[".repeat(100), "]".repeat(100));
+        assert!(parse(&ok).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_bad_unicode_escapes() {
+        for src in [
+            "\"\\uD83D\\u0041\"",
+            "\"\\u+041\"",
+            "\"\\uD83D\"",
+            "\"\\u00e\"",
+        ] {
+            assert!(parse(src).is_err(), "{src}");
+        }
+        assert_eq!(
+            parse("\"\\uD83D\\u0041\"").unwrap_err().message,
+            "invalid surrogate pair"
+        );
+    }
+}
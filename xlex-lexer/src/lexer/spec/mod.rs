@@ -0,0 +1,609 @@
+mod json;
+mod node;
+#[cfg(feature = "toml")]
+mod toml;
+
+use super::classifier::Classification;
+use super::config::Config;
+use super::grouping::Grouping;
+use super::normalize::Normalization;
+use super::replace::ReplaceMode;
+use super::rules::Rule;
+use super::table::{ClassifierBuilder, TableClassifier};
+use super::token::{BaseKind, Newlines, TokenKind};
+
+use node::Node;
+use std::{borrow::Cow, collections::HashMap, error, fmt, mem};
+use unicode_general_category::GeneralCategory;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct KindId(u16);
+
+impl KindId {
+    #[inline]
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecError {
+    pub key: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl SpecError {
+    #[inline]
+    pub(crate) fn new(key: &str, line: usize, message: impl Into<String>) -> Self {
+        Self {
+            key: key.to_string(),
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.key.is_empty() {
+            true => write!(f, "line {}: {}", self.line, self.message),
+            false => write!(f, "line {}: `{}`: {}", self.line, self.key, self.message),
+        }
+    }
+}
+
+impl error::Error for SpecError {}
+
+pub struct Spec {
+    pub config: Config<KindId>,
+    pub classifier: TableClassifier<KindId>,
+    kinds: Vec<String>,
+}
+
+impl Spec {
+    #[inline]
+    pub fn from_json(src: &str) -> Result<Self, SpecError> {
+        Loader::default().load(json::parse(src)?)
+    }
+
+    #[cfg(feature = "toml")]
+    #[inline]
+    pub fn from_toml(src: &str) -> Result<Self, SpecError> {
+        Loader::default().load(toml::parse(src)?)
+    }
+
+    #[inline]
+    pub fn kind(&self, name: &str) -> Option<KindId> {
+        self.kinds
+            .iter()
+            .position(|k| k == name)
+            .map(|i| KindId(i as u16))
+    }
+
+    #[inline]
+    pub fn name(&self, kind: KindId) -> Option<&str> {
+        self.kinds.get(kind.index()).map(String::as_str)
+    }
+}
+
+enum Target {
+    Base(BaseKind),
+    Custom(KindId),
+}
+
+#[derive(Default)]
+struct Loader {
+    kinds: Vec<String>,
+    ids: HashMap<String, KindId>,
+    config: Config<KindId>,
+    builder: ClassifierBuilder<KindId>,
+}
+
+const SECTIONS: [&str; 5] = ["class", "literal", "comment", "string", "keywords"];
+
+impl Loader {
+    fn load(mut self, root: Node) -> Result<Spec, SpecError> {
+        let entries = root.table("")?;
+        for (key, node) in entries
+            .iter()
+            .filter(|(k, _)| SECTIONS.contains(&k.as_str()))
+        {
+            match key.as_str() {
+                "keywords" => self.keywords(node)?,
+                _ => {
+                    for (i, item) in node.array(key)?.iter().enumerate() {
+                        let path = format!("{key}[{i}]");
+                        match key.as_str() {
+                            "class" => self.class(&path, item)?,
+                            _ => self.rule(key, &path, item)?,
+                        }
+                    }
+                }
+            }
+        }
+
+        for (key, node) in entries {
+            match key.as_str() {
+                k if SECTIONS.contains(&k) => {}
+                "skip" => self.skip(key, node)?,
+                "grouping" => self.grouping(key, node)?,
+                "merge" => self.merge(key, node)?,
+                "group_symbols" => self.config.group_symbols = node.bool(key)?,
+                "case_fold" => self.config.case_fold = node.bool(key)?,
+                "skip_replaced" => self.config.skip_replaced = node.bool(key)?,
+                "strict" => self.config.strict = node.bool(key)?,
+                "keywords_ignore_case" if node.bool(key)? => {
+                    self.config = mem::take(&mut self.config).with_case_insensitive_keywords();
+                }
+                "keywords_ignore_case" => {}
+                "newlines" => {
+                    self.config.newlines = Some(match node.str(key)? {
+                        "per-break" => Newlines::PerBreak,
+                        "per-run" => Newlines::PerRun,
+                        _ => return Err(node.error(key, "expected `per-break` or `per-run`")),
+                    })
+                }
+                "normalization" => {
+                    self.config.normalization = Some(match node.str(key)? {
+                        "nfc" => Normalization::Nfc,
+                        "nfd" => Normalization::Nfd,
+                        "nfkc" => Normalization::Nfkc,
+                        "nfkd" => Normalization::Nfkd,
+                        _ => return Err(node.error(key, "unknown normalization form")),
+                    })
+                }
+                "replace" => {
+                    self.config.replace = match node.str(key)? {
+                        "per-char" => ReplaceMode::PerChar,
+                        "in-run" => ReplaceMode::InRun,
+                        "whole-run" => ReplaceMode::WholeRun,
+                        _ => return Err(node.error(key, "unknown replace mode")),
+                    }
+                }
+                _ => return Err(node.error(key, "unknown key")),
+            }
+        }
+
+        self.config.kind_index = Some((self.kinds.len(), |k: &KindId| k.index()));
+        Ok(Spec {
+            config: self.config,
+            classifier: self.builder.build(),
+            kinds: self.kinds,
+        })
+    }
+
+    fn declare(&mut self, key: &str, node: &Node) -> Result<KindId, SpecError> {
+        let name = node.str(key)?;
+        if base_kind(name).is_some() {
+            return Err(node.error(key, format!("`{name}` is a base kind name")));
+        }
+        if let Some(&id) = self.ids.get(name) {
+            return Ok(id);
+        }
+        if self.kinds.len() >= u16::MAX as usize {
+            return Err(node.error(key, "too many kinds"));
+        }
+        let id = KindId(self.kinds.len() as u16);
+        self.kinds.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        Ok(id)
+    }
+
+    fn resolve(&self, key: &str, node: &Node, name: &str) -> Result<Target, SpecError> {
+        if let Some(base) = base_kind(name) {
+            return Ok(Target::Base(base));
+        }
+        match self.ids.get(name) {
+            Some(&id) => Ok(Target::Custom(id)),
+            None => Err(node.error(key, format!("unknown kind `{name}`"))),
+        }
+    }
+
+    fn token_kind(&mut self, path: &str, item: &Node) -> Result<TokenKind<KindId>, SpecError> {
+        let mut base = BaseKind::Symbol;
+        let mut custom = None;
+        for (key, node) in item.table(path)? {
+            match key.as_str() {
+                "base" => base = base_of(&format!("{path}.base"), node)?,
+                "kind" => custom = Some(self.declare(&format!("{path}.kind"), node)?),
+                _ => {}
+            }
+        }
+        Ok(TokenKind::new(base, custom))
+    }
+
+    fn class(&mut self, path: &str, item: &Node) -> Result<(), SpecError> {
+        let kind = self.token_kind(path, item)?;
+        let mut repl = None;
+        let mut matchers = Vec::new();
+        for (key, node) in item.table(path)? {
+            let key_path = format!("{path}.{key}");
+            match key.as_str() {
+                "base" | "kind" => {}
+                "replace" => repl = Some(Cow::Owned(node.str(&key_path)?.to_string())),
+                "chars" | "ranges" | "categories" => matchers.push((key.as_str(), key_path, node)),
+                _ => return Err(node.error(&key_path, "unknown key")),
+            }
+        }
+        if matchers.is_empty() {
+            return Err(item.error(path, "class needs `chars`, `ranges` or `categories`"));
+        }
+
+        let class: Classification<KindId> = (kind.base, kind.custom, repl);
+        let mut builder = mem::take(&mut self.builder);
+        for (key, path, node) in matchers {
+            match key {
+                "chars" => builder = builder.chars(node.str(&path)?.chars(), class.clone()),
+                "ranges" => {
+                    for (i, range) in node.array(&path)?.iter().enumerate() {
+                        let path = format!("{path}[{i}]");
+                        let (lo, hi) = char_range(&path, range)?;
+                        builder = builder.range(lo..=hi, class.clone());
+                    }
+                }
+                _ => {
+                    for (i, cat) in node.array(&path)?.iter().enumerate() {
+                        let path = format!("{path}[{i}]");
+                        let category = category(cat.str(&path)?)
+                            .ok_or_else(|| cat.error(&path, "unknown general category"))?;
+                        builder = builder.category(category, class.clone());
+                    }
+                }
+            }
+        }
+        self.builder = builder;
+        Ok(())
+    }
+
+    fn rule(&mut self, section: &str, path: &str, item: &Node) -> Result<(), SpecError> {
+        let kind = self.token_kind(path, item)?;
+        let mut fields = Vec::new();
+        for (key, node) in item.table(path)? {
+            let key_path = format!("{path}.{key}");
+            let known: &[&str] = match section {
+                "literal" => &["text"],
+                "comment" => &["line", "standalone", "start", "end", "nested"],
+                _ => &["quote", "open", "close", "escape", "multiline"],
+            };
+            match key.as_str() {
+                "base" | "kind" => {}
+                k if known.contains(&k) => fields.push((k, key_path, node)),
+                _ => return Err(node.error(&key_path, "unknown key")),
+            }
+        }
+        let get = |name: &str| fields.iter().find(|(k, ..)| *k == name);
+        let text = |name: &str| -> Result<Option<String>, SpecError> {
+            match get(name) {
+                Some((_, path, node)) => match node.str(path)? {
+                    "" => Err(node.error(path, "must not be empty")),
+                    s => Ok(Some(s.to_string())),
+                },
+                None => Ok(None),
+            }
+        };
+        let flag = |name: &str| match get(name) {
+            Some((_, path, node)) => node.bool(path),
+            None => Ok(false),
+        };
+        let missing = |what: &str| item.error(path, format!("missing `{what}`"));
+
+        let rule = match section {
+            "literal" => Rule::Literal {
+                text: text("text")?.ok_or_else(|| missing("text"))?,
+                kind,
+            },
+            "comment" => match (text("line")?, text("start")?, text("end")?) {
+                (Some(start), None, None) => Rule::LineComment {
+                    start,
+                    standalone: flag("standalone")?,
+                    kind,
+                },
+                (None, Some(start), Some(end)) if get("standalone").is_none() => {
+                    Rule::BlockComment {
+                        start,
+                        end,
+                        nested: flag("nested")?,
+                        kind,
+                    }
+                }
+                _ => return Err(item.error(path, "expected either `line` or `start` and `end`")),
+            },
+            _ => {
+                let (open, close) = match (text("quote")?, text("open")?, text("close")?) {
+                    (Some(q), None, None) => (q.clone(), q),
+                    (None, Some(open), Some(close)) => (open, close),
+                    _ => {
+                        return Err(
+                            item.error(path, "expected either `quote` or `open` and `close`")
+                        )
+                    }
+                };
+                let escape = match get("escape") {
+                    Some((_, path, node)) => Some(node.char(path)?),
+                    None => None,
+                };
+                Rule::Quoted {
+                    open,
+                    close,
+                    escape,
+                    multiline: flag("multiline")?,
+                    kind,
+                }
+            }
+        };
+        self.config.rules.push(rule);
+        Ok(())
+    }
+
+    fn keywords(&mut self, node: &Node) -> Result<(), SpecError> {
+        for (name, words) in node.table("keywords")? {
+            let path = format!("keywords.{name}");
+            let id = self.declare(
+                &path,
+                &Node::new(node::Value::Str(name.clone()), words.line),
+            )?;
+            let words = words.strings(&path)?;
+            self.config = mem::take(&mut self.config).keywords(words.into_iter().map(|w| (w, id)));
+        }
+        Ok(())
+    }
+
+    fn skip(&mut self, key: &str, node: &Node) -> Result<(), SpecError> {
+        self.config.skip_base.clear();
+        self.config.skip_custom.clear();
+        for (i, item) in node.array(key)?.iter().enumerate() {
+            let path = format!("{key}[{i}]");
+            match self.resolve(&path, item, item.str(&path)?)? {
+                Target::Base(base) => {
+                    self.config.skip_base.insert(base);
+                }
+                Target::Custom(id) => {
+                    self.config.skip_custom.insert(Some(id));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn grouping(&mut self, key: &str, node: &Node) -> Result<(), SpecError> {
+        for (name, value) in node.table(key)? {
+            let path = format!("{key}.{name}");
+            let policy = match &value.value {
+                node::Value::Int(n) if *n > 0 => Grouping::UpTo(*n as usize),
+                node::Value::Str(s) => match s.as_str() {
+                    "always" => Grouping::Always,
+                    "never" => Grouping::Never,
+                    "identical" => Grouping::Identical,
+                    _ => return Err(value.error(&path, "unknown grouping policy")),
+                },
+                _ => return Err(value.error(&path, "expected a policy name or a positive integer")),
+            };
+            match self.resolve(&path, value, name)? {
+                Target::Base(base) => self.config.grouping.insert(base, policy),
+                Target::Custom(id) => self.config.grouping_custom.insert(id, policy),
+            };
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, key: &str, node: &Node) -> Result<(), SpecError> {
+        for (i, pair) in node.array(key)?.iter().enumerate() {
+            let path = format!("{key}[{i}]");
+            let [into, from] = pair.array(&path)? else {
+                return Err(pair.error(&path, "expected a pair of base kinds"));
+            };
+            let into = base_of(&format!("{path}[0]"), into)?;
+            let from = base_of(&format!("{path}[1]"), from)?;
+            self.config.merges.insert((into, from));
+        }
+        Ok(())
+    }
+}
+
+fn base_kind(name: &str) -> Option<BaseKind> {
+    Some(match name {
+        "number" => BaseKind::Number,
+        "symbol" => BaseKind::Symbol,
+        "space" => BaseKind::Space,
+        "word" => BaseKind::Word,
+        "indent" => BaseKind::Indent,
+        "dedent" => BaseKind::Dedent,
+        "newline" => BaseKind::Newline,
+        "eof" => BaseKind::Eof,
+        "error" => BaseKind::Error,
+        _ => return None,
+    })
+}
+
+fn base_of(path: &str, node: &Node) -> Result<BaseKind, SpecError> {
+    base_kind(node.str(path)?).ok_or_else(|| node.error(path, "unknown base kind"))
+}
+
+fn char_range(path: &str, node: &Node) -> Result<(char, char), SpecError> {
+    let s = node.str(path)?;
+    let chars: Vec<char> = s.chars().collect();
+    match chars[..] {
+        [c] => Ok((c, c)),
+        [lo, '-', hi] if lo <= hi => Ok((lo, hi)),
+        _ => Err(node.error(path, "expected a character or a range like `a-z`")),
+    }
+}
+
+fn category(abbr: &str) -> Option<GeneralCategory> {
+    use GeneralCategory::*;
+    Some(match abbr {
+        "Lu" => UppercaseLetter,
+        "Ll" => LowercaseLetter,
+        "Lt" => TitlecaseLetter,
+        "Lm" => ModifierLetter,
+        "Lo" => OtherLetter,
+        "Mn" => NonspacingMark,
+        "Mc" => SpacingMark,
+        "Me" => EnclosingMark,
+        "Nd" => DecimalNumber,
+        "Nl" => LetterNumber,
+        "No" => OtherNumber,
+        "Pc" => ConnectorPunctuation,
+        "Pd" => DashPunctuation,
+        "Ps" => OpenPunctuation,
+        "Pe" => ClosePunctuation,
+        "Pi" => InitialPunctuation,
+        "Pf" => FinalPunctuation,
+        "Po" => OtherPunctuation,
+        "Sm" => MathSymbol,
+        "Sc" => CurrencySymbol,
+        "Sk" => ModifierSymbol,
+        "So" => OtherSymbol,
+        "Zs" => SpaceSeparator,
+        "Zl" => LineSeparator,
+        "Zp" => ParagraphSeparator,
+        "Cc" => Control,
+        "Cf" => Format,
+        "Cs" => Surrogate,
+        "Co" => PrivateUse,
+        "Cn" => Unassigned,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::inline::LexerInline;
+
+    const JSON: &str = r##"{
+        "skip": ["space", "comment"],
+        "group_symbols": true,
+        "grouping": { "op": "identical" },
+        "class": [
+            { "base": "word", "chars": "_$" },
+            { "base": "symbol", "kind": "op", "chars": "+-*/=<>" },
+            { "base": "space", "chars": "\t", "replace": " " }
+        ],
+        "literal": [{ "text": "=>", "kind": "arrow" }],
+        "comment": [
+            { "line": "#", "standalone": true, "kind": "comment" },
+            { "start": "/*", "end": "*/", "kind": "comment" }
+        ],
+        "string": [{ "quote": "\"", "escape": "\\", "kind": "string", "base": "word" }],
+        "keywords": { "keyword": ["let", "fn"] }
+    }"##;
+
+    fn lex(spec: &Spec, input: &str) -> Vec<(String, String)> {
+        LexerInline::new(&spec.config, &spec.classifier, input)
+            .map(|t| {
+                let kind = match t.kind.custom {
+                    Some(id) => spec.name(id).unwrap().to_string(),
+                    None => format!("{:?}", t.kind.base).to_lowercase(),
+                };
+                (t.text.into_owned(), kind)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_load_json() {
+        let spec = Spec::from_json(JSON).unwrap();
+        assert_eq!(spec.kind("op"), Some(KindId(0)));
+        assert_eq!(spec.name(KindId(4)), Some("keyword"));
+
+        let tokens = lex(&spec, "let $a_1 => \"x\\\"y\" a#b # note\n++= /* c */ fn");
+        let expect = [
+            ("let", "keyword"),
+            ("$a_", "word"),
+            ("1", "number"),
+            ("=>", "arrow"),
+            ("\"x\\\"y\"", "string"),
+            ("a", "word"),
+            ("#", "symbol"),
+            ("b", "word"),
+            ("++", "op"),
+            ("=", "op"),
+            ("fn", "keyword"),
+        ];
+        let expect: Vec<_> = expect
+            .iter()
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+            .collect();
+        assert_eq!(tokens, expect);
+    }
+
+    #[test]
+    fn test_errors_report_key_and_line() {
+        let err = Spec::from_json("{\n  \"skip\": [\"space\",\n    \"nope\"]\n}")
+            .err()
+            .unwrap();
+        assert_eq!(err.key, "skip[1]");
+        assert_eq!(err.line, 3);
+        assert_eq!(err.to_string(), "line 3: `skip[1]`: unknown kind `nope`");
+
+        let err = Spec::from_json(
+            "{\"class\": [\n{\"base\": \"word\", \"chars\": \"_\"},\n{\"base\": \"wrd\", \"chars\": \"x\"}]}",
+        )
+        .err()
+        .unwrap();
+        assert_eq!((err.key.as_str(), err.line), ("class[1].base", 3));
+
+        let err = Spec::from_json("{\"grouping\": {\"word\": 0}}")
+            .err()
+            .unwrap();
+        assert_eq!(err.key, "grouping.word");
+
+        let err = Spec::from_json("{\"string\": [{\"open\": \"'\"}]}")
+            .err()
+            .unwrap();
+        assert_eq!(err.key, "string[0]");
+
+        let err = Spec::from_json("{\"colour\": true}").err().unwrap();
+        assert_eq!(err.message, "unknown key");
+    }
+
+    #[test]
+    fn test_large_spec_loads() {
+        let literals: Vec<_> = (0..20_000)
+            .map(|i| format!("{{\"text\": \"@{i}\", \"kind\": \"k{i}\"}}"))
+            .collect();
+        let src = format!("{{\"literal\": [\n{}\n]}}", literals.join(",\n"));
+        let spec = Spec::from_json(&src).unwrap();
+        assert_eq!(spec.kinds.len(), 20_000);
+
+        let src = src.replacen("\"@19999\"", "19999", 1);
+        assert_eq!(Spec::from_json(&src).err().unwrap().line, 20_001);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_load_toml() {
+        let src = r#"
+skip = ["space", "comment"]
+merge = [["word", "number"]]
+
+[grouping]
+number = 2
+
+[[comment]]
+line = "--"
+kind = "comment"
+
+[[string]]
+quote = "'"
+kind = "string"
+
+[keywords]
+keyword = ["select", "from"]
+"#;
+        let spec = Spec::from_toml(src).unwrap();
+        let tokens: Vec<_> = lex(&spec, "select a1 from 'x' -- c\n123")
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect();
+        assert_eq!(tokens, ["select", "a1", "from", "'x'", "12", "3"]);
+
+        let err = Spec::from_toml("skip = [\"space\"]\n\n[grouping]\nword = \"sometimes\"\n")
+            .err()
+            .unwrap();
+        assert_eq!((err.key.as_str(), err.line), ("grouping.word", 4));
+        assert_eq!(Spec::from_toml("skip = [\n").err().unwrap().line, 1);
+    }
+}
@@ -0,0 +1,91 @@
+use super::SpecError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+    Array(Vec<Node>),
+    Table(Vec<(String, Node)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Node {
+    pub(crate) value: Value,
+    pub(crate) line: usize,
+}
+
+impl Node {
+    #[inline]
+    pub(crate) fn new(value: Value, line: usize) -> Self {
+        Self { value, line }
+    }
+
+    #[inline]
+    pub(crate) fn error(&self, key: &str, message: impl Into<String>) -> SpecError {
+        SpecError::new(key, self.line, message)
+    }
+
+    pub(crate) fn str(&self, key: &str) -> Result<&str, SpecError> {
+        match &self.value {
+            Value::Str(s) => Ok(s),
+            _ => Err(self.error(key, "expected a string")),
+        }
+    }
+
+    pub(crate) fn char(&self, key: &str) -> Result<char, SpecError> {
+        let s = self.str(key)?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(self.error(key, "expected a single character")),
+        }
+    }
+
+    pub(crate) fn bool(&self, key: &str) -> Result<bool, SpecError> {
+        match self.value {
+            Value::Bool(b) => Ok(b),
+            _ => Err(self.error(key, "expected a boolean")),
+        }
+    }
+
+    pub(crate) fn array(&self, key: &str) -> Result<&[Node], SpecError> {
+        match &self.value {
+            Value::Array(items) => Ok(items),
+            _ => Err(self.error(key, "expected an array")),
+        }
+    }
+
+    pub(crate) fn table(&self, key: &str) -> Result<&[(String, Node)], SpecError> {
+        match &self.value {
+            Value::Table(entries) => Ok(entries),
+            _ => Err(self.error(key, "expected a table")),
+        }
+    }
+
+    pub(crate) fn strings(&self, key: &str) -> Result<Vec<&str>, SpecError> {
+        self.array(key)?
+            .iter()
+            .enumerate()
+            .map(|(i, n)| n.str(&format!("{key}[{i}]")))
+            .collect()
+    }
+}
+
+pub(crate) struct Lines {
+    starts: Vec<usize>,
+}
+
+impl Lines {
+    pub(crate) fn new(src: &str) -> Self {
+        let breaks = src.match_indices('\n').map(|(i, _)| i + 1);
+        Self {
+            starts: std::iter::once(0).chain(breaks).collect(),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn line(&self, offset: usize) -> usize {
+        self.starts.partition_point(|&start| start <= offset)
+    }
+}
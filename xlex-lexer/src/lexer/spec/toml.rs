@@ -0,0 +1,48 @@
+use super::node::{Lines, Node, Value};
+use super::SpecError;
+
+use toml::de::{DeTable, DeValue};
+use toml::Spanned;
+
+pub(crate) fn parse(src: &str) -> Result<Node, SpecError> {
+    let lines = Lines::new(src);
+    let table = DeTable::parse(src).map_err(|e| {
+        let line = e.span().map_or(1, |s| lines.line(s.start));
+        SpecError::new("", line, e.message())
+    })?;
+    let line = lines.line(table.span().start);
+    Ok(Node::new(convert_table(&lines, table.get_ref())?, line))
+}
+
+fn convert_table(lines: &Lines, table: &DeTable<'_>) -> Result<Value, SpecError> {
+    let mut entries = Vec::with_capacity(table.len());
+    for (key, value) in table.iter() {
+        entries.push((
+            key.get_ref().to_string(),
+            convert(lines, key.get_ref(), value)?,
+        ));
+    }
+    Ok(Value::Table(entries))
+}
+
+fn convert(lines: &Lines, key: &str, value: &Spanned<DeValue<'_>>) -> Result<Node, SpecError> {
+    let line = lines.line(value.span().start);
+    let value = match value.get_ref() {
+        DeValue::String(s) => Value::Str(s.to_string()),
+        DeValue::Integer(i) => i64::from_str_radix(i.as_str(), i.radix())
+            .map(Value::Int)
+            .map_err(|_| SpecError::new(key, line, "integer out of range"))?,
+        DeValue::Boolean(b) => Value::Bool(*b),
+        DeValue::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| convert(lines, key, item))
+                .collect::<Result<_, _>>()?,
+        ),
+        DeValue::Table(table) => convert_table(lines, table)?,
+        DeValue::Float(_) | DeValue::Datetime(_) => {
+            return Err(SpecError::new(key, line, "unsupported value type"));
+        }
+    };
+    Ok(Node::new(value, line))
+}
@@ -0,0 +1,186 @@
+use super::classifier::Classification;
+use super::context::ContextClassifier;
+
+use std::hash::Hash;
+
+pub trait StatefulClassifier {
+    type Custom: Copy + Eq + Hash;
+    type Snapshot: Clone;
+
+    fn classify(&mut self, c: char) -> Classification<Self::Custom>;
+
+    fn reset(&mut self);
+
+    fn snapshot(&self) -> Self::Snapshot;
+
+    fn restore(&mut self, snapshot: Self::Snapshot);
+}
+
+pub struct Stateful<S> {
+    inner: S,
+}
+
+impl<S: StatefulClassifier + Clone> Stateful<S> {
+    #[inline]
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: StatefulClassifier + Clone> ContextClassifier for Stateful<S> {
+    type Custom = S::Custom;
+    type State = S;
+    type Snapshot = S::Snapshot;
+
+    #[inline]
+    fn start(&self) -> S {
+        let mut state = self.inner.clone();
+        state.reset();
+        state
+    }
+
+    #[inline]
+    fn classify_at(
+        &self,
+        state: &mut S,
+        _prev: Option<char>,
+        c: char,
+        _next: &str,
+    ) -> Classification<S::Custom> {
+        state.classify(c)
+    }
+
+    #[inline]
+    fn snapshot(&self, state: &S) -> S::Snapshot {
+        state.snapshot()
+    }
+
+    #[inline]
+    fn restore(&self, state: &mut S, snapshot: S::Snapshot) {
+        state.restore(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::config::Config;
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::stream::LexerStream;
+    use crate::lexer::token::{classify_base, BaseKind};
+    use crate::lexer::trivia::TriviaMode;
+    use std::io::{BufReader, Cursor};
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    struct Nested;
+
+    #[derive(Default, Clone)]
+    struct Depth {
+        depth: usize,
+    }
+
+    impl StatefulClassifier for Depth {
+        type Custom = Nested;
+        type Snapshot = usize;
+
+        fn classify(&mut self, c: char) -> Classification<Nested> {
+            match c {
+                '(' => self.depth += 1,
+                ')' => self.depth = self.depth.saturating_sub(1),
+                c if c.is_alphabetic() && self.depth > 0 => {
+                    return (BaseKind::Word, Some(Nested), None)
+                }
+                _ => {}
+            }
+            (classify_base(c), None, None)
+        }
+
+        fn reset(&mut self) {
+            self.depth = 0;
+        }
+
+        fn snapshot(&self) -> usize {
+            self.depth
+        }
+
+        fn restore(&mut self, depth: usize) {
+            self.depth = depth;
+        }
+    }
+
+    fn nested(cls: &Stateful<Depth>, input: &str) -> Vec<String> {
+        let cfg = Config::default();
+        LexerInline::new(&cfg, cls, input)
+            .filter(|t| t.kind.custom == Some(Nested))
+            .map(|t| t.text.into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_depth_is_tracked_once_per_char() {
+        let cls = Stateful::new(Depth::default());
+        assert_eq!(nested(&cls, "f(x)g (a (b) c) d"), ["x", "a", "b", "c"]);
+        let cfg = Config::default();
+        let mut lexer = LexerInline::new(&cfg, &cls, "((a) b");
+        lexer.by_ref().for_each(drop);
+        assert_eq!(lexer.checkpoint().snapshot, 1);
+        assert_eq!(cls.into_inner().depth, 0);
+    }
+
+    #[test]
+    fn test_lexers_own_state() {
+        let cfg = Config::default();
+        let cls = Stateful::new(Depth::default());
+        let mut outer = LexerInline::new(&cfg, &cls, "(a");
+        let mut inner = LexerInline::new(&cfg, &cls, "x");
+        assert_eq!(outer.next().unwrap().text, "(");
+        assert_eq!(inner.next().unwrap().kind.custom, None);
+        assert_eq!(outer.next().unwrap().kind.custom, Some(Nested));
+    }
+
+    #[test]
+    fn test_lexers_reset_state() {
+        let cls = Stateful::new(Depth::default());
+        assert_eq!(nested(&cls, "((a"), ["a"]);
+        assert!(nested(&cls, "b").is_empty());
+    }
+
+    #[test]
+    fn test_stream_matches_inline_across_refills() {
+        let input = "f(x)g (a ((b)) c) d";
+        let cfg = Config::default();
+        let cls = Stateful::new(Depth::default());
+        let inline: Vec<_> = LexerInline::new(&cfg, &cls, input)
+            .map(|t| (t.text.into_owned(), t.kind))
+            .collect();
+        let reader = BufReader::with_capacity(1, Cursor::new(input));
+        let stream: Vec<_> = LexerStream::new(&cfg, &cls, reader)
+            .map(|t| (t.text.into_owned(), t.kind))
+            .collect();
+        assert_eq!(stream, inline);
+    }
+
+    #[test]
+    fn test_checkpoint_restores_classifier_state() {
+        for cfg in [
+            Config::default(),
+            Config::default().with_trivia(TriviaMode::Trailing),
+        ] {
+            let cls = Stateful::new(Depth::default());
+            let mut lexer = LexerInline::new(&cfg, &cls, "(a b) c");
+            lexer.next();
+            let cp = lexer.checkpoint();
+            assert_eq!(cp.snapshot, 1);
+            let first: Vec<_> = lexer.by_ref().map(|t| t.kind).collect();
+            lexer.restore(cp);
+            let second: Vec<_> = lexer.map(|t| t.kind).collect();
+            assert_eq!(first, second);
+            assert_eq!(first[0].custom, Some(Nested));
+        }
+    }
+}
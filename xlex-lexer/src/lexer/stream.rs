@@ -1,120 +1,291 @@
-use super::classifier::Classifier;
+use super::checkpoint::Checkpoint;
+use super::compiled::CompiledConfig;
 use super::config::Config;
-use super::token::{BaseKind, Token, TokenKind};
+use super::context::ContextClassifier;
+use super::error::{LexError, LexErrorKind};
+use super::scan::{Resume, Scanner, State, Step, Tail};
+use super::token::Token;
+use super::trivia::{Attach, TriviaMode};
 
 use std::borrow::Cow;
-use std::io::BufRead;
-use std::{hash::Hash, str};
+use std::io::{self, BufRead, Seek, SeekFrom};
+use std::{hash::Hash, mem, str};
 
 pub struct LexerStream<'a, TK, CL, BR>
 where
     BR: BufRead,
     TK: Copy + Eq + Hash,
-    CL: Classifier<Custom = TK>,
+    CL: ContextClassifier<Custom = TK> + ?Sized,
 {
-    config: &'a Config<TK>,
+    config: Cow<'a, CompiledConfig<'a, TK>>,
     classifier: &'a CL,
+    context: CL::State,
 
     reader: BR,
-    pos: usize,
+    buf: String,
+    base: usize,
+    partial: Vec<u8>,
+    invalid: usize,
+    eof: bool,
+
+    state: State,
+    resume: Option<Resume<TK, CL::Snapshot>>,
+    attach: Attach<'static, TK, CL::Snapshot>,
+    error: Option<LexError>,
 }
 
 impl<'a, TK, CL, BR> LexerStream<'a, TK, CL, BR>
 where
     BR: BufRead,
     TK: Copy + Eq + Hash,
-    CL: Classifier<Custom = TK>,
+    CL: ContextClassifier<Custom = TK> + ?Sized,
 {
     #[inline]
     pub fn new(config: &'a Config<TK>, classifier: &'a CL, reader: BR) -> Self {
+        Self::with_config(Cow::Owned(CompiledConfig::new(config)), classifier, reader)
+    }
+
+    #[inline]
+    pub fn from_compiled(
+        config: &'a CompiledConfig<'a, TK>,
+        classifier: &'a CL,
+        reader: BR,
+    ) -> Self {
+        Self::with_config(Cow::Borrowed(config), classifier, reader)
+    }
+
+    fn with_config(
+        config: Cow<'a, CompiledConfig<'a, TK>>,
+        classifier: &'a CL,
+        reader: BR,
+    ) -> Self {
         LexerStream {
             config,
             classifier,
+            context: classifier.start(),
             reader,
-            pos: 0,
+            buf: String::new(),
+            base: 0,
+            partial: Vec::new(),
+            invalid: 0,
+            eof: false,
+            state: State::default(),
+            resume: None,
+            attach: Attach::default(),
+            error: None,
+        }
+    }
+
+    #[inline]
+    pub fn checkpoint(&self) -> Checkpoint<CL::Snapshot> {
+        match self.config.trivia {
+            Some(_) => self
+                .attach
+                .checkpoint(|| self.classifier.snapshot(&self.context)),
+            None => Checkpoint {
+                state: self.state,
+                snapshot: self.classifier.snapshot(&self.context),
+            },
         }
     }
+
+    #[inline]
+    pub fn error(&self) -> Option<&LexError> {
+        self.error.as_ref()
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &BR {
+        &self.reader
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut BR {
+        &mut self.reader
+    }
+
+    fn fill(&mut self) {
+        let consumed = self.buf.len();
+        let used = self.state.pos - self.base;
+        if used > 0 && used * 2 >= consumed {
+            self.buf.drain(..used);
+            self.base += used;
+        }
+
+        let data = match self.reader.fill_buf() {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => return,
+            Err(e) => {
+                self.error = Some(LexError::new(LexErrorKind::Io(e.kind()), self.state.pos));
+                return;
+            }
+        };
+        if data.is_empty() {
+            self.invalid += self.partial.len();
+            self.partial.clear();
+            self.eof = true;
+            return;
+        }
+
+        let taken = if self.partial.is_empty() {
+            match str::from_utf8(data) {
+                Ok(s) => {
+                    self.buf.push_str(s);
+                    data.len()
+                }
+                Err(e) => {
+                    let valid = e.valid_up_to();
+                    self.buf.push_str(str::from_utf8(&data[..valid]).unwrap());
+                    match e.error_len() {
+                        Some(len) => {
+                            self.invalid = len;
+                            valid + len
+                        }
+                        None => {
+                            self.partial.extend_from_slice(&data[valid..]);
+                            data.len()
+                        }
+                    }
+                }
+            }
+        } else {
+            let need = utf_char_len(self.partial[0]) - self.partial.len();
+            let taken = data
+                .iter()
+                .take(need)
+                .take_while(|&&b| b & 0xC0 == 0x80)
+                .count();
+            self.partial.extend_from_slice(&data[..taken]);
+
+            if taken == need {
+                match str::from_utf8(&self.partial) {
+                    Ok(s) => self.buf.push_str(s),
+                    Err(_) => self.invalid = self.partial.len(),
+                }
+                self.partial.clear();
+            } else if taken < data.len() {
+                self.invalid = self.partial.len();
+                self.partial.clear();
+            }
+            taken
+        };
+        self.reader.consume(taken);
+
+        let read = self.base + self.buf.len() + self.invalid + self.partial.len();
+        match self.config.limits.max_input {
+            Some(max) if read > max => {
+                self.error = Some(LexError::new(LexErrorKind::InputTooLarge, max));
+            }
+            _ => {}
+        }
+    }
+
+    #[inline]
+    fn skip_invalid(&mut self) {
+        self.state.pos += self.invalid;
+        self.state.prev = None;
+        self.invalid = 0;
+        self.buf.clear();
+        self.base = self.state.pos;
+    }
+}
+
+impl<'a, TK, CL, BR> LexerStream<'a, TK, CL, BR>
+where
+    BR: BufRead + Seek,
+    TK: Copy + Eq + Hash,
+    CL: ContextClassifier<Custom = TK> + ?Sized,
+{
+    pub fn restore(&mut self, checkpoint: Checkpoint<CL::Snapshot>) -> io::Result<()> {
+        let read = self.base + self.buf.len() + self.invalid + self.partial.len();
+        let delta = checkpoint.state.pos as i64 - read as i64;
+        self.reader.seek(SeekFrom::Current(delta))?;
+
+        self.buf.clear();
+        self.base = checkpoint.state.pos;
+        self.partial.clear();
+        self.invalid = 0;
+        self.eof = false;
+        self.state = checkpoint.state;
+        self.resume = None;
+        self.attach.reset(checkpoint.state);
+        self.classifier
+            .restore(&mut self.context, checkpoint.snapshot);
+        self.error = None;
+        Ok(())
+    }
 }
 
 impl<'a, TK, CL, BR> Iterator for LexerStream<'a, TK, CL, BR>
 where
     BR: BufRead,
     TK: Copy + Eq + Hash,
-    CL: Classifier<Custom = TK>,
+    CL: ContextClassifier<Custom = TK> + ?Sized,
 {
     type Item = Token<'static, TK>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        let mut before = self.state;
+        let mut saved = None;
         loop {
-            let buf = match self.reader.fill_buf() {
-                Ok(b) if !b.is_empty() => b,
-                _ => return None,
-            };
-
-            let position = self.pos;
-            let char_len = utf_char_len(buf[0]);
-
-            if char_len > buf.len() {
-                self.reader.consume(1);
-                self.pos += 1;
-                continue;
+            if self.error.is_some() {
+                return self.attach.flush();
             }
-            
-            let ch = match str::from_utf8(&buf[..char_len])
-                .ok()
-                .and_then(|s| s.chars().next())
-            {
-                Some(c) => c,
-                None => {
-                    self.reader.consume(char_len);
-                    self.pos += char_len;
-                    continue;
-                }
+            let tail = if self.invalid > 0 {
+                Tail::Break
+            } else if self.eof {
+                Tail::Eof
+            } else {
+                Tail::More
             };
 
-            let (bk, ck, repl) = self.classifier.classify(ch);
-            let kind = TokenKind::new(bk, ck);
-
-            if let Some(repl) = repl {
-                self.reader.consume(char_len);
-                self.pos += char_len;
-                
-                return Some(Token {
-                    text: Cow::Owned(repl.into_owned()),
-                    kind,
-                    start: position,
-                });
-            }
-            if self.config.should_skip(bk, ck) {
-                let token_len = scan_token(buf, char_len, bk, ck, self.classifier);
-                self.reader.consume(token_len);
-                self.pos += token_len;
-                continue;
-            }
-
-            let token_len;
-            if (bk == BaseKind::Symbol && self.config.group_symbols) || bk != BaseKind::Symbol {
-                token_len = scan_token(buf, char_len, bk, ck, self.classifier);
-            } else {
-                token_len = char_len;
+            if self.resume.is_none() {
+                before = self.state;
+                saved = match self.config.trivia {
+                    Some(TriviaMode::Trailing) => Some(self.classifier.snapshot(&self.context)),
+                    _ => None,
+                };
             }
-
-            let text = match str::from_utf8(&buf[..token_len]) {
-                Ok(s) => Cow::Owned(s.to_string()),
-                Err(_) => {
-                    self.reader.consume(token_len);
-                    self.pos += token_len;
-                    continue;
+            match Scanner::new(&self.config, self.classifier).step(
+                &mut self.state,
+                &mut self.context,
+                &mut self.resume,
+                &self.buf,
+                self.base,
+                tail,
+            ) {
+                Step::Token(raw) => {
+                    let range = raw.start - self.base..raw.end - self.base;
+                    let source = self.buf[range].to_string();
+                    let mut token = Token::new(raw.kind, source, raw.start, raw.end);
+                    if let Some(repl) = raw.repl {
+                        token.original =
+                            Some(mem::replace(&mut token.text, Cow::Owned(repl.into_owned())));
+                    }
+                    let token = self.config.finish(token);
+                    let Some(mode) = self.config.trivia else {
+                        return Some(token);
+                    };
+                    if let Some(token) =
+                        self.attach
+                            .token(mode, token, before, self.state, saved.take())
+                    {
+                        return Some(token);
+                    }
                 }
-            };
-
-            self.reader.consume(token_len);
-            self.pos += token_len;
-            return Some(Token {
-                text,
-                kind,
-                start: position,
-            });
+                Step::Skipped(raw) => {
+                    let range = raw.start - self.base..raw.end - self.base;
+                    let piece =
+                        Token::new(raw.kind, self.buf[range].to_string(), raw.start, raw.end);
+                    if let Some(mode) = self.config.trivia {
+                        self.attach.trivia(mode, piece);
+                    }
+                }
+                Step::More => self.fill(),
+                Step::Done if self.invalid > 0 => self.skip_invalid(),
+                Step::Done => return self.attach.finish(self.state.pos, self.state),
+                Step::Error(e) => self.error = Some(e),
+            }
         }
     }
 }
@@ -132,50 +303,14 @@ fn utf_char_len(f: u8) -> usize {
     }
 }
 
-#[inline]
-fn scan_token<TK, CL>(
-    buf: &[u8],
-    mut token_len: usize,
-    base: BaseKind,
-    custom: Option<TK>,
-    classifier: &CL,
-) -> usize
-where
-    TK: Copy + Eq + Hash,
-    CL: Classifier<Custom = TK>,
-{
-    while token_len < buf.len() {
-        let len = utf_char_len(buf[token_len]);
-        if token_len + len > buf.len() {
-            break;
-        }
-
-        let ch = match str::from_utf8(&buf[token_len..token_len + len])
-            .ok()
-            .and_then(|s| s.chars().next())
-        {
-            Some(c) => c,
-            None => break,
-        };
-
-        let (bk, ck, next_repl) = classifier.classify(ch);
-        if next_repl.is_some() || bk != base || ck != custom {
-            break;
-        }
-        
-        token_len += len;
-    }
-    
-    token_len
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::lexer::classifier::{Classifier, DefaultClassifier};
     use crate::lexer::{
         config::Config,
-        token::{classify_base, BaseKind},
+        normalize::Normalization,
+        token::{classify_base, BaseKind, Newlines, TokenKind},
     };
     use std::borrow::Cow;
     use std::io::Cursor;
@@ -287,4 +422,106 @@ mod tests {
             .collect();
         assert_eq!(out, ["abc"]);
     }
+
+    #[test]
+    fn test_normalization_joins_combining_marks() {
+        let cfg = Config::default().with_normalization(Normalization::Nfc);
+        let cls = DefaultClassifier;
+        let reader = Cursor::new("cafe\u{301} ＡＢＣ");
+        let out: Vec<_> = LexerStream::new(&cfg, &cls, reader).collect();
+        assert_eq!(
+            out.iter().map(|t| t.text.as_ref()).collect::<Vec<_>>(),
+            &["café", "ＡＢＣ"]
+        );
+        assert_eq!(out[1].start, 7);
+    }
+
+    #[test]
+    fn test_case_folding_with_keywords() {
+        let cfg = Config::default()
+            .keywords([("GRÜSSE", MyCustom::LetterA)])
+            .with_case_insensitive_keywords()
+            .with_case_folding();
+        let cls = MyClassifier;
+        let reader = Cursor::new("Grüße y");
+        let out: Vec<_> = LexerStream::new(&cfg, &cls, reader).collect();
+        assert_eq!(out[0].text, "grüsse");
+        assert_eq!(
+            out[0].kind,
+            TokenKind::new(BaseKind::Word, Some(MyCustom::LetterA))
+        );
+        assert_eq!(out[1].kind, TokenKind::WORD);
+    }
+
+    #[test]
+    fn test_checkpoint_restore_by_seeking() {
+        let cfg = Config::default();
+        let cls = MyClassifier;
+        let reader = std::io::BufReader::with_capacity(4, Cursor::new("x a7 yy\tz"));
+        let mut lexer = LexerStream::new(&cfg, &cls, reader);
+
+        lexer.next();
+        let cp = lexer.checkpoint();
+        let first: Vec<_> = lexer.by_ref().collect();
+
+        lexer.restore(cp).unwrap();
+        let second: Vec<_> = lexer.collect();
+        assert_eq!(first, second);
+        assert_eq!(second[0].start, 2);
+    }
+
+    #[test]
+    fn test_tokens_span_buffer_refills() {
+        let cfg = Config::default();
+        let cls = DefaultClassifier;
+        let reader = std::io::BufReader::with_capacity(2, Cursor::new("hello wörld 12345"));
+        let out: Vec<_> = LexerStream::new(&cfg, &cls, reader)
+            .map(|t| (t.text.into_owned(), t.start))
+            .collect();
+        assert_eq!(
+            out,
+            [
+                ("hello".to_string(), 0),
+                ("wörld".to_string(), 6),
+                ("12345".to_string(), 13)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8_breaks_tokens() {
+        let cfg = Config::default();
+        let cls = DefaultClassifier;
+        let reader = Cursor::new(b"ab\xFFcd \xE2\x82z".to_vec());
+        let out: Vec<_> = LexerStream::new(&cfg, &cls, reader)
+            .map(|t| (t.text.into_owned(), t.start))
+            .collect();
+        assert_eq!(
+            out,
+            [
+                ("ab".to_string(), 0),
+                ("cd".to_string(), 3),
+                ("z".to_string(), 8)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_newlines_crlf_across_refills() {
+        let cfg = Config::default().with_newlines(Newlines::PerBreak);
+        let cls = DefaultClassifier;
+        let reader = std::io::BufReader::with_capacity(2, Cursor::new("ab\r\n\rc"));
+        let out: Vec<_> = LexerStream::new(&cfg, &cls, reader)
+            .map(|t| (t.text.into_owned(), t.kind.base))
+            .collect();
+        assert_eq!(
+            out,
+            [
+                ("ab".to_string(), BaseKind::Word),
+                ("\r\n".to_string(), BaseKind::Newline),
+                ("\r".to_string(), BaseKind::Newline),
+                ("c".to_string(), BaseKind::Word)
+            ]
+        );
+    }
 }
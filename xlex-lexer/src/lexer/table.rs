@@ -0,0 +1,262 @@
+use super::classifier::{Classification, Classifier};
+use super::token::classify_base;
+
+use std::{hash::Hash, marker::PhantomData, ops::RangeInclusive};
+use unicode_general_category::{get_general_category, GeneralCategory};
+
+const NONE: u32 = u32::MAX;
+
+pub struct FnClassifier<TK, F> {
+    f: F,
+    _custom: PhantomData<fn() -> TK>,
+}
+
+impl<TK, F> FnClassifier<TK, F>
+where
+    TK: Copy + Eq + Hash,
+    F: Fn(char) -> Classification<TK>,
+{
+    #[inline]
+    pub fn new(f: F) -> Self {
+        Self {
+            f,
+            _custom: PhantomData,
+        }
+    }
+}
+
+impl<TK, F> Classifier for FnClassifier<TK, F>
+where
+    TK: Copy + Eq + Hash,
+    F: Fn(char) -> Classification<TK>,
+{
+    type Custom = TK;
+
+    #[inline]
+    fn classify(&self, c: char) -> Classification<TK> {
+        (self.f)(c)
+    }
+}
+
+enum Rule {
+    Range(u32, u32),
+    Category(GeneralCategory),
+}
+
+impl Rule {
+    #[inline]
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            Rule::Range(lo, hi) => (lo..=hi).contains(&(c as u32)),
+            Rule::Category(cat) => get_general_category(c) == cat,
+        }
+    }
+}
+
+pub struct ClassifierBuilder<TK: Copy + Eq + Hash> {
+    rules: Vec<(Rule, Classification<TK>)>,
+}
+
+impl<TK: Copy + Eq + Hash> Default for ClassifierBuilder<TK> {
+    #[inline]
+    fn default() -> Self {
+        Self { rules: Vec::new() }
+    }
+}
+
+impl<TK: Copy + Eq + Hash> ClassifierBuilder<TK> {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn chars<IT>(mut self, chars: IT, class: Classification<TK>) -> Self
+    where
+        IT: IntoIterator<Item = char>,
+    {
+        for c in chars {
+            self.rules
+                .push((Rule::Range(c as u32, c as u32), class.clone()));
+        }
+        self
+    }
+
+    #[inline]
+    pub fn range(mut self, range: RangeInclusive<char>, class: Classification<TK>) -> Self {
+        let (lo, hi) = range.into_inner();
+        if lo <= hi {
+            self.rules.push((Rule::Range(lo as u32, hi as u32), class));
+        }
+        self
+    }
+
+    #[inline]
+    pub fn category(mut self, category: GeneralCategory, class: Classification<TK>) -> Self {
+        self.rules.push((Rule::Category(category), class));
+        self
+    }
+
+    pub fn build(self) -> TableClassifier<TK> {
+        let mut ascii = [NONE; 128];
+        for (b, slot) in ascii.iter_mut().enumerate() {
+            let c = b as u8 as char;
+            if let Some(i) = self.rules.iter().rposition(|(r, _)| r.matches(c)) {
+                *slot = i as u32;
+            }
+        }
+
+        let mut ranges: Vec<(u32, u32, u32)> = Vec::new();
+        let mut categories = Vec::new();
+        for (i, (rule, _)) in self.rules.iter().enumerate() {
+            match *rule {
+                Rule::Range(lo, hi) if hi >= 128 => {
+                    insert(&mut ranges, lo.max(128), hi, i as u32);
+                }
+                Rule::Range(..) => {}
+                Rule::Category(cat) => categories.push((cat, i as u32)),
+            }
+        }
+
+        TableClassifier {
+            ascii,
+            ranges,
+            categories,
+            classes: self.rules.into_iter().map(|(_, class)| class).collect(),
+        }
+    }
+}
+
+fn insert(ranges: &mut Vec<(u32, u32, u32)>, lo: u32, hi: u32, index: u32) {
+    let mut out = Vec::with_capacity(ranges.len() + 2);
+    for &(a, b, i) in ranges.iter() {
+        if b < lo || a > hi {
+            out.push((a, b, i));
+            continue;
+        }
+        if a < lo {
+            out.push((a, lo - 1, i));
+        }
+        if b > hi {
+            out.push((hi + 1, b, i));
+        }
+    }
+    out.push((lo, hi, index));
+    out.sort_unstable_by_key(|&(a, ..)| a);
+    *ranges = out;
+}
+
+pub struct TableClassifier<TK: Copy + Eq + Hash> {
+    ascii: [u32; 128],
+    ranges: Vec<(u32, u32, u32)>,
+    categories: Vec<(GeneralCategory, u32)>,
+    classes: Vec<Classification<TK>>,
+}
+
+impl<TK: Copy + Eq + Hash> TableClassifier<TK> {
+    #[inline]
+    fn lookup(&self, c: char) -> u32 {
+        let code = c as u32;
+        if code < 128 {
+            return self.ascii[code as usize];
+        }
+
+        let i = self.ranges.partition_point(|&(lo, ..)| lo <= code);
+        let mut found = match i.checked_sub(1).map(|i| self.ranges[i]) {
+            Some((_, hi, index)) if code <= hi => index,
+            _ => NONE,
+        };
+        if !self.categories.is_empty() {
+            let cat = get_general_category(c);
+            for &(want, index) in &self.categories {
+                if want == cat && (found == NONE || index > found) {
+                    found = index;
+                }
+            }
+        }
+        found
+    }
+}
+
+impl<TK: Copy + Eq + Hash> Classifier for TableClassifier<TK> {
+    type Custom = TK;
+
+    #[inline]
+    fn classify(&self, c: char) -> Classification<TK> {
+        match self.lookup(c) {
+            NONE => (classify_base(c), None, None),
+            index => self.classes[index as usize].clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::config::Config;
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::token::BaseKind;
+    use std::borrow::Cow;
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    enum Kind {
+        Greek,
+        Op,
+        Emoji,
+    }
+
+    #[test]
+    fn test_fn_classifier() {
+        let cls = FnClassifier::new(|c| match c {
+            '-' => (BaseKind::Word, None::<()>, None),
+            c => (classify_base(c), None, None),
+        });
+        let cfg = Config::default();
+        let tokens: Vec<_> = LexerInline::new(&cfg, &cls, "foo-bar baz")
+            .map(|t| t.text)
+            .collect();
+        assert_eq!(tokens, ["foo-bar", "baz"]);
+    }
+
+    #[test]
+    fn test_builder_chars_ranges_and_categories() {
+        let cls = ClassifierBuilder::new()
+            .chars("+-*/".chars(), (BaseKind::Symbol, Some(Kind::Op), None))
+            .range(
+                '\u{1F600}'..='\u{1F64F}',
+                (BaseKind::Word, Some(Kind::Emoji), None),
+            )
+            .category(
+                GeneralCategory::LowercaseLetter,
+                (BaseKind::Word, None, None),
+            )
+            .range('α'..='ω', (BaseKind::Word, Some(Kind::Greek), None))
+            .build();
+
+        assert_eq!(cls.classify('+').1, Some(Kind::Op));
+        assert_eq!(cls.classify('😀').1, Some(Kind::Emoji));
+        assert_eq!(cls.classify('β').1, Some(Kind::Greek));
+        assert_eq!(cls.classify('é'), (BaseKind::Word, None, None));
+        assert_eq!(cls.classify('Ω'), (BaseKind::Word, None, None));
+        assert_eq!(cls.classify('3'), (BaseKind::Number, None, None));
+    }
+
+    #[test]
+    fn test_later_rules_override_earlier_ones() {
+        let cls = ClassifierBuilder::new()
+            .range('a'..='z', (BaseKind::Symbol, Some(Kind::Op), None))
+            .chars(['q'], (BaseKind::Word, None, Some(Cow::Borrowed("Q"))))
+            .range(
+                '\u{100}'..='\u{200}',
+                (BaseKind::Word, Some(Kind::Greek), None),
+            )
+            .range('\u{150}'..='\u{160}', (BaseKind::Number, None, None))
+            .build();
+
+        assert_eq!(cls.classify('a').1, Some(Kind::Op));
+        assert_eq!(cls.classify('q').2, Some(Cow::Borrowed("Q")));
+        assert_eq!(cls.classify('\u{14F}').1, Some(Kind::Greek));
+        assert_eq!(cls.classify('\u{155}').0, BaseKind::Number);
+        assert_eq!(cls.classify('\u{161}').1, Some(Kind::Greek));
+        assert_eq!(cls.ranges.len(), 3);
+    }
+}
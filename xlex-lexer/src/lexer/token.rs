@@ -1,6 +1,7 @@
 use super::classifier::Classifier;
+use super::trivia::Trivia;
 
-use std::{borrow::Cow, hash::Hash};
+use std::{borrow::Cow, hash::Hash, ops::Range};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Token<'a, TK: Copy + Eq + Hash> {
@@ -8,6 +9,10 @@ pub struct Token<'a, TK: Copy + Eq + Hash> {
     pub text: Cow<'a, str>,
 
     pub start: usize,
+    pub end: usize,
+    pub original: Option<Cow<'a, str>>,
+
+    pub trivia: Option<Box<Trivia<'a, TK>>>,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -16,6 +21,19 @@ pub enum BaseKind {
     Symbol,
     Space,
     Word,
+
+    Indent,
+    Dedent,
+    Newline,
+    Eof,
+
+    Error,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Newlines {
+    PerBreak,
+    PerRun,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -41,6 +59,26 @@ impl<TK: Copy + Eq + Hash> TokenKind<TK> {
         base: BaseKind::Word,
         custom: None,
     };
+    pub const INDENT: Self = Self {
+        base: BaseKind::Indent,
+        custom: None,
+    };
+    pub const DEDENT: Self = Self {
+        base: BaseKind::Dedent,
+        custom: None,
+    };
+    pub const NEWLINE: Self = Self {
+        base: BaseKind::Newline,
+        custom: None,
+    };
+    pub const EOF: Self = Self {
+        base: BaseKind::Eof,
+        custom: None,
+    };
+    pub const ERROR: Self = Self {
+        base: BaseKind::Error,
+        custom: None,
+    };
 
     #[inline]
     pub fn new(base: BaseKind, custom: Option<TK>) -> Self {
@@ -48,18 +86,62 @@ impl<TK: Copy + Eq + Hash> TokenKind<TK> {
     }
 
     #[inline]
-    pub fn matches(&self, c: char, classifier: &impl Classifier<Custom = TK>) -> bool {
+    pub fn matches(&self, c: char, classifier: &(impl Classifier<Custom = TK> + ?Sized)) -> bool {
         is_match(self.base, self.custom, c, classifier)
     }
 
     pub fn predicate<'a>(
         &'a self,
-        classifier: &'a impl Classifier<Custom = TK>,
+        classifier: &'a (impl Classifier<Custom = TK> + ?Sized),
     ) -> impl Fn(char) -> bool + 'a {
         move |ch| is_match(self.base, self.custom, ch, classifier)
     }
 }
 
+impl<'a, TK: Copy + Eq + Hash> Token<'a, TK> {
+    #[inline]
+    pub fn new(
+        kind: TokenKind<TK>,
+        text: impl Into<Cow<'a, str>>,
+        start: usize,
+        end: usize,
+    ) -> Self {
+        Self {
+            kind,
+            text: text.into(),
+            start,
+            end,
+            original: None,
+            trivia: None,
+        }
+    }
+
+    #[inline]
+    pub fn source(&self) -> &str {
+        self.original.as_deref().unwrap_or(&self.text)
+    }
+
+    #[inline]
+    pub fn span(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    #[inline]
+    pub fn leading(&self) -> &[Token<'a, TK>] {
+        self.trivia.as_ref().map_or(&[], |t| &t.leading)
+    }
+
+    #[inline]
+    pub fn trailing(&self) -> &[Token<'a, TK>] {
+        self.trivia.as_ref().map_or(&[], |t| &t.trailing)
+    }
+
+    #[inline]
+    pub(crate) fn trivia_mut(&mut self) -> &mut Trivia<'a, TK> {
+        self.trivia.get_or_insert_with(Box::default)
+    }
+}
+
 #[inline]
 pub fn classify_base(c: char) -> BaseKind {
     if c.is_alphabetic() {
@@ -73,12 +155,20 @@ pub fn classify_base(c: char) -> BaseKind {
     }
 }
 
+#[inline]
+pub fn is_line_break(c: char) -> bool {
+    matches!(
+        c,
+        '\n' | '\u{000B}' | '\u{000C}' | '\r' | '\u{0085}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
 #[inline]
 fn is_match<TK: Copy + Eq + Hash>(
     base: BaseKind,
     custom: Option<TK>,
     ch: char,
-    classifier: &impl Classifier<Custom = TK>,
+    classifier: &(impl Classifier<Custom = TK> + ?Sized),
 ) -> bool {
     let (b, cust, _repl) = classifier.classify(ch);
     base == b && custom == cust
@@ -147,4 +237,16 @@ mod tests {
         assert_eq!(classify_base(' '), BaseKind::Space);
         assert_eq!(classify_base('@'), BaseKind::Symbol);
     }
+
+    #[test]
+    fn test_is_line_break() {
+        for c in [
+            '\n', '\r', '\u{000B}', '\u{000C}', '\u{0085}', '\u{2028}', '\u{2029}',
+        ] {
+            assert!(is_line_break(c));
+            assert_eq!(classify_base(c), BaseKind::Space);
+        }
+        assert!(!is_line_break('\t'));
+        assert!(!is_line_break(' '));
+    }
 }
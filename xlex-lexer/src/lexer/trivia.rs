@@ -0,0 +1,220 @@
+use super::checkpoint::Checkpoint;
+use super::scan::State;
+use super::token::{BaseKind, Token, TokenKind};
+
+use std::{hash::Hash, mem};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TriviaMode {
+    Leading,
+    Trailing,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia<'a, TK: Copy + Eq + Hash> {
+    pub leading: Vec<Token<'a, TK>>,
+    pub trailing: Vec<Token<'a, TK>>,
+}
+
+impl<'a, TK: Copy + Eq + Hash> Default for Trivia<'a, TK> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            leading: Vec::new(),
+            trailing: Vec::new(),
+        }
+    }
+}
+
+pub(crate) struct Attach<'a, TK: Copy + Eq + Hash, S> {
+    pending: Vec<Token<'a, TK>>,
+    held: Option<Token<'a, TK>>,
+    finished: bool,
+    resume: State,
+    saved: Option<S>,
+}
+
+impl<'a, TK: Copy + Eq + Hash, S> Default for Attach<'a, TK, S> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            pending: Vec::new(),
+            held: None,
+            finished: false,
+            resume: State::default(),
+            saved: None,
+        }
+    }
+}
+
+impl<'a, TK: Copy + Eq + Hash, S: Clone> Attach<'a, TK, S> {
+    #[inline]
+    pub(crate) fn trivia(&mut self, mode: TriviaMode, piece: Token<'a, TK>) {
+        match (&mut self.held, mode) {
+            (Some(held), TriviaMode::Trailing) => held.trivia_mut().trailing.push(piece),
+            _ => self.pending.push(piece),
+        }
+    }
+
+    pub(crate) fn token(
+        &mut self,
+        mode: TriviaMode,
+        mut token: Token<'a, TK>,
+        before: State,
+        after: State,
+        saved: Option<S>,
+    ) -> Option<Token<'a, TK>> {
+        if !self.pending.is_empty() {
+            token.trivia_mut().leading = mem::take(&mut self.pending);
+        }
+        match mode {
+            TriviaMode::Leading => {
+                self.resume = after;
+                self.saved = None;
+                Some(token)
+            }
+            TriviaMode::Trailing => {
+                self.resume = before;
+                self.saved = saved;
+                self.held.replace(token)
+            }
+        }
+    }
+
+    pub(crate) fn finish(&mut self, at: usize, state: State) -> Option<Token<'a, TK>> {
+        if let Some(held) = self.held.take() {
+            return Some(held);
+        }
+        self.resume = state;
+        self.saved = None;
+        if self.finished || self.pending.is_empty() {
+            return None;
+        }
+        self.finished = true;
+
+        let mut eof = Token::new(TokenKind::new(BaseKind::Eof, None), "", at, at);
+        eof.trivia_mut().leading = mem::take(&mut self.pending);
+        Some(eof)
+    }
+
+    #[inline]
+    pub(crate) fn flush(&mut self) -> Option<Token<'a, TK>> {
+        self.held.take()
+    }
+
+    #[inline]
+    pub(crate) fn reset(&mut self, state: State) {
+        self.pending.clear();
+        self.held = None;
+        self.finished = false;
+        self.resume = state;
+        self.saved = None;
+    }
+
+    #[inline]
+    pub(crate) fn checkpoint(&self, snapshot: impl FnOnce() -> S) -> Checkpoint<S> {
+        Checkpoint {
+            state: self.resume,
+            snapshot: self.saved.clone().unwrap_or_else(snapshot),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::classifier::DefaultClassifier;
+    use crate::lexer::config::Config;
+    use crate::lexer::error::LexErrorKind;
+    use crate::lexer::inline::LexerInline;
+    use crate::lexer::stream::LexerStream;
+    use std::io::{BufReader, Cursor};
+
+    fn rebuild<TK: Copy + Eq + Hash>(tokens: &[Token<'_, TK>]) -> String {
+        let mut out = String::new();
+        for token in tokens {
+            token.leading().iter().for_each(|t| out.push_str(&t.text));
+            out.push_str(&token.text);
+            token.trailing().iter().for_each(|t| out.push_str(&t.text));
+        }
+        out
+    }
+
+    const INPUT: &str = "  let x\t= 1;\n\n  foo (bar)  ";
+
+    #[test]
+    fn test_leading_trivia_round_trip() {
+        let cfg = Config::default().with_trivia(TriviaMode::Leading);
+        let cls = DefaultClassifier;
+        let tokens: Vec<_> = LexerInline::new(&cfg, &cls, INPUT).collect();
+
+        assert_eq!(rebuild(&tokens), INPUT);
+        assert_eq!(tokens[0].text, "let");
+        assert_eq!(tokens[0].leading()[0].span(), 0..2);
+        assert!(tokens[0].trailing().is_empty());
+
+        let eof = tokens.last().unwrap();
+        assert_eq!(eof.kind.base, BaseKind::Eof);
+        assert_eq!(eof.span(), 27..27);
+        assert_eq!(eof.leading()[0].text, "  ");
+    }
+
+    #[test]
+    fn test_trailing_trivia_round_trip() {
+        let cfg = Config::default().with_trivia(TriviaMode::Trailing);
+        let cls = DefaultClassifier;
+        let tokens: Vec<_> = LexerInline::new(&cfg, &cls, INPUT).collect();
+
+        assert_eq!(rebuild(&tokens), INPUT);
+        assert_eq!(tokens[0].leading()[0].text, "  ");
+        assert_eq!(tokens[0].trailing()[0].text, " ");
+        assert_eq!(tokens.last().unwrap().text, ")");
+        assert_eq!(tokens.last().unwrap().trailing()[0].span(), 25..27);
+    }
+
+    #[test]
+    fn test_stream_trivia_round_trip() {
+        let cfg = Config::default().with_trivia(TriviaMode::Trailing);
+        let cls = DefaultClassifier;
+        let reader = BufReader::with_capacity(3, Cursor::new(INPUT));
+        let tokens: Vec<_> = LexerStream::new(&cfg, &cls, reader).collect();
+        assert_eq!(rebuild(&tokens), INPUT);
+    }
+
+    #[test]
+    fn test_checkpoint_with_held_token() {
+        let cfg = Config::default().with_trivia(TriviaMode::Trailing);
+        let cls = DefaultClassifier;
+        let mut lexer = LexerInline::new(&cfg, &cls, INPUT);
+
+        lexer.next();
+        let cp = lexer.checkpoint();
+        let first: Vec<_> = lexer.by_ref().collect();
+        lexer.restore(cp);
+        let second: Vec<_> = lexer.collect();
+        assert_eq!(first, second);
+        assert_eq!(second[0].text, "x");
+    }
+
+    #[test]
+    fn test_error_keeps_held_token() {
+        let word = TokenKind::new(BaseKind::Word, None);
+        let input = "a b \"oops";
+        for mode in [None, Some(TriviaMode::Leading), Some(TriviaMode::Trailing)] {
+            let mut cfg = Config::default().quoted("\"", "\"", None, true, word);
+            if let Some(mode) = mode {
+                cfg = cfg.with_trivia(mode);
+            }
+            let cls = DefaultClassifier;
+            let mut inline = LexerInline::new(&cfg, &cls, input);
+            let texts: Vec<_> = inline.by_ref().map(|t| t.text.into_owned()).collect();
+            assert_eq!(texts, ["a", "b"], "{mode:?}");
+            assert_eq!(inline.error().unwrap().kind, LexErrorKind::Unterminated);
+
+            let mut stream = LexerStream::new(&cfg, &cls, input.as_bytes());
+            let texts: Vec<_> = stream.by_ref().map(|t| t.text.into_owned()).collect();
+            assert_eq!(texts, ["a", "b"], "{mode:?}");
+            assert_eq!(stream.error().unwrap().kind, LexErrorKind::Unterminated);
+        }
+    }
+}
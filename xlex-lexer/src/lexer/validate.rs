@@ -0,0 +1,435 @@
+use super::compiled::CompiledConfig;
+use super::config::Config;
+use super::context::ContextClassifier;
+use super::grouping::Grouping;
+use super::rules::Found;
+use super::scan::Scanner;
+use super::token::{BaseKind, TokenKind};
+
+use std::{collections::HashSet, error, fmt, hash::Hash};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue<TK: Copy + Eq + Hash> {
+    SkipsEverything,
+    SkippedReplacement { kind: TokenKind<TK>, example: char },
+    EmptyRule { rule: usize },
+    ShadowedRule { rule: usize, by: usize },
+    InvalidRuleStart { rule: usize },
+    UnreachableKeyword { text: String },
+    SkippedKeyword { text: String, kind: TK },
+    DeadMerge { into: BaseKind, from: BaseKind },
+}
+
+impl<TK: Copy + Eq + Hash> Issue<TK> {
+    #[inline]
+    pub fn severity(&self) -> Severity {
+        match self {
+            Issue::SkipsEverything
+            | Issue::EmptyRule { .. }
+            | Issue::ShadowedRule { .. }
+            | Issue::UnreachableKeyword { .. } => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+impl<TK: Copy + Eq + Hash + fmt::Debug> fmt::Display for Issue<TK> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::SkipsEverything => f.write_str("every token kind is skipped"),
+            Issue::SkippedReplacement { kind, example } => write!(
+                f,
+                "U+{:04X} is replaced but its kind {kind:?} is skipped; the replacement is emitted",
+                *example as u32
+            ),
+            Issue::EmptyRule { rule } => write!(f, "rule {rule} has an empty start"),
+            Issue::ShadowedRule { rule, by } => {
+                write!(f, "rule {rule} is always matched as rule {by} instead")
+            }
+            Issue::InvalidRuleStart { rule } => {
+                write!(f, "rule {rule} starts with a character rejected as invalid")
+            }
+            Issue::UnreachableKeyword { text } => {
+                write!(f, "keyword `{text}` is never lexed as a single token")
+            }
+            Issue::SkippedKeyword { text, kind } => write!(
+                f,
+                "keyword `{text}` has skipped kind {kind:?}; keywords are assigned after skipping"
+            ),
+            Issue::DeadMerge { into, from } => {
+                write!(
+                    f,
+                    "merging {from:?} into {into:?} has no effect, {into:?} never groups"
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report<TK: Copy + Eq + Hash> {
+    pub issues: Vec<Issue<TK>>,
+}
+
+impl<TK: Copy + Eq + Hash> Report<TK> {
+    pub(crate) fn new<C>(config: &Config<TK>, classifier: &C) -> Self
+    where
+        C: ContextClassifier<Custom = TK> + ?Sized,
+    {
+        let compiled = CompiledConfig::new(config);
+        let mut report = Self { issues: Vec::new() };
+        report.classes(&compiled, classifier);
+        report.rules(&compiled);
+        report.keywords(&compiled, classifier);
+        report.merges(config);
+        report
+    }
+
+    #[inline]
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    #[inline]
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    #[inline]
+    pub fn errors(&self) -> impl Iterator<Item = &Issue<TK>> {
+        self.issues
+            .iter()
+            .filter(|i| i.severity() == Severity::Error)
+    }
+
+    #[inline]
+    pub fn into_result(self) -> Result<Self, Self> {
+        match self.has_errors() {
+            true => Err(self),
+            false => Ok(self),
+        }
+    }
+
+    fn classes<C>(&mut self, config: &CompiledConfig<'_, TK>, classifier: &C)
+    where
+        C: ContextClassifier<Custom = TK> + ?Sized,
+    {
+        let scanner = Scanner::new(config, classifier);
+        let mut emitted = config.offside.is_some()
+            || config
+                .rules
+                .iter()
+                .any(|r| !config.should_skip(r.kind().base, r.kind().custom));
+        let mut replaced = HashSet::new();
+        let mut state = classifier.start();
+        let start = classifier.snapshot(&state);
+        for c in char::MIN..=char::MAX {
+            let (bk, ck, repl) = scanner.classify(&mut state, None, c, "");
+            classifier.restore(&mut state, start.clone());
+            if bk == BaseKind::Error && config.strict {
+                continue;
+            }
+            let kind = TokenKind::new(bk, ck);
+            if !config.skips(bk, ck, repl.is_some()) {
+                emitted = true;
+            }
+            let conflict = repl.is_some() && !config.skip_replaced && config.should_skip(bk, ck);
+            if conflict && replaced.insert(kind) {
+                self.issues
+                    .push(Issue::SkippedReplacement { kind, example: c });
+            }
+        }
+        if !emitted {
+            self.issues.insert(0, Issue::SkipsEverything);
+        }
+    }
+
+    fn rules(&mut self, config: &CompiledConfig<'_, TK>) {
+        for (i, rule) in config.rules.iter().enumerate() {
+            let start = rule.start();
+            let Some(first) = start.chars().next() else {
+                self.issues.push(Issue::EmptyRule { rule: i });
+                continue;
+            };
+            if config.is_invalid(first) {
+                self.issues.push(Issue::InvalidRuleStart { rule: i });
+            }
+            if let Found::Rule(found) = config.rule(start, first, None, false) {
+                let by = config.rules.iter().position(|r| std::ptr::eq(r, found));
+                if let Some(by) = by.filter(|&by| by != i) {
+                    self.issues.push(Issue::ShadowedRule { rule: i, by });
+                }
+            }
+        }
+    }
+
+    fn keywords<C>(&mut self, config: &CompiledConfig<'_, TK>, classifier: &C)
+    where
+        C: ContextClassifier<Custom = TK> + ?Sized,
+    {
+        let scanner = Scanner::new(config, classifier);
+        let mut keywords: Vec<_> = config.keywords.iter().collect();
+        keywords.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        for (text, kind) in keywords {
+            if config.skip_custom.contains(&Some(*kind)) {
+                self.issues.push(Issue::SkippedKeyword {
+                    text: text.clone(),
+                    kind: *kind,
+                });
+            }
+            if !reachable(config, &scanner, text) {
+                self.issues
+                    .push(Issue::UnreachableKeyword { text: text.clone() });
+            }
+        }
+    }
+
+    fn merges(&mut self, config: &Config<TK>) {
+        let mut merges: Vec<_> = config.merges.iter().copied().collect();
+        merges.sort_unstable_by_key(|&(into, from)| (into as usize, from as usize));
+        for (into, from) in merges {
+            if into != from && config.grouping(into, None) == Grouping::Never {
+                self.issues.push(Issue::DeadMerge { into, from });
+            }
+        }
+    }
+}
+
+fn reachable<TK, C>(
+    config: &CompiledConfig<'_, TK>,
+    scanner: &Scanner<'_, TK, C>,
+    text: &str,
+) -> bool
+where
+    TK: Copy + Eq + Hash,
+    C: ContextClassifier<Custom = TK> + ?Sized,
+{
+    let mut chars = text.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    if !matches!(config.rule(text, first, None, false), Found::None) {
+        return false;
+    }
+    let mut state = scanner.classifier.start();
+    let (bk, ck, repl) = scanner.classify(&mut state, None, first, "");
+    if ck.is_some() || repl.is_some() || bk == BaseKind::Error {
+        return false;
+    }
+    let grouping = config.grouping(bk, ck);
+    let mut prev = first;
+    for (count, c) in (1..).zip(chars) {
+        let (nb, nc, repl) = scanner.classify(&mut state, Some(prev), c, "");
+        let kind = (nb == bk && nc == ck) || config.merges(bk, nb);
+        if !kind || repl.is_some() || !grouping.extends(count, first, c) {
+            return false;
+        }
+        prev = c;
+    }
+    true
+}
+
+impl<TK: Copy + Eq + Hash + fmt::Debug> fmt::Display for Report<TK> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, issue) in self.issues.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            let level = match issue.severity() {
+                Severity::Warning => "warning",
+                Severity::Error => "error",
+            };
+            write!(f, "{level}: {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<TK: Copy + Eq + Hash + fmt::Debug> error::Error for Report<TK> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::classifier::{Classification, Classifier, DefaultClassifier};
+    use crate::lexer::invalid::InvalidChar;
+    use crate::lexer::token::{classify_base, Newlines};
+    use std::borrow::Cow;
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    enum Kind {
+        Op,
+        Comment,
+        Keyword,
+    }
+
+    struct Base;
+    impl Classifier for Base {
+        type Custom = Kind;
+
+        fn classify(&self, c: char) -> Classification<Kind> {
+            match c {
+                '\t' => (BaseKind::Space, None, Some(Cow::Borrowed("    "))),
+                c => (classify_base(c), None, None),
+            }
+        }
+    }
+
+    #[test]
+    fn test_default_config_is_clean() {
+        let cfg = Config::default();
+        assert!(cfg.validate(&DefaultClassifier).is_clean());
+    }
+
+    #[test]
+    fn test_skipping_everything_is_an_error() {
+        let all = [
+            BaseKind::Number,
+            BaseKind::Symbol,
+            BaseKind::Word,
+            BaseKind::Error,
+        ];
+        let cfg = Config::default().skip_base(all);
+        let report = cfg.validate(&DefaultClassifier);
+        assert_eq!(report.issues, [Issue::SkipsEverything]);
+        assert!(report.clone().into_result().is_err());
+        assert_eq!(report.to_string(), "error: every token kind is skipped");
+
+        let cfg = cfg.with_newlines(Newlines::PerRun);
+        assert!(!cfg.validate(&DefaultClassifier).has_errors());
+    }
+
+    #[test]
+    fn test_replacement_of_skipped_chars() {
+        let cfg = Config::default();
+        let report = cfg.validate(&Base);
+        assert_eq!(
+            report.issues,
+            [Issue::SkippedReplacement {
+                kind: TokenKind::SPACE,
+                example: '\t'
+            }]
+        );
+        assert!(!report.has_errors());
+        assert!(cfg.with_skip_replaced().validate(&Base).is_clean());
+    }
+
+    #[test]
+    fn test_rule_conflicts() {
+        let op = TokenKind::new(BaseKind::Symbol, Some(Kind::Op));
+        let comment = TokenKind::new(BaseKind::Symbol, Some(Kind::Comment));
+        let cfg = Config::default()
+            .with_skip_replaced()
+            .literal("-", op)
+            .line_comment("--", comment)
+            .literal("--", op)
+            .literal("", op)
+            .literal("\u{1}", op)
+            .reject([InvalidChar::Control]);
+        let issues = cfg.validate(&Base).issues;
+        assert_eq!(
+            issues,
+            [
+                Issue::ShadowedRule { rule: 2, by: 1 },
+                Issue::EmptyRule { rule: 3 },
+                Issue::InvalidRuleStart { rule: 4 },
+            ]
+        );
+
+        let cfg = Config::default()
+            .with_skip_replaced()
+            .literal("<<=", op)
+            .literal("<", op)
+            .literal("<<", op);
+        assert!(cfg.validate(&Base).is_clean());
+    }
+
+    #[test]
+    fn test_keyword_and_merge_conflicts() {
+        let cfg = Config::default()
+            .with_skip_replaced()
+            .skip_custom([Kind::Comment])
+            .keywords([
+                ("let", Kind::Keyword),
+                ("if-else", Kind::Keyword),
+                ("rem", Kind::Comment),
+                ("x1", Kind::Keyword),
+            ])
+            .group(BaseKind::Symbol, Grouping::Never)
+            .merge(BaseKind::Symbol, BaseKind::Word);
+        let issues = cfg.validate(&Base).issues;
+        assert_eq!(
+            issues,
+            [
+                Issue::UnreachableKeyword {
+                    text: "if-else".into()
+                },
+                Issue::SkippedKeyword {
+                    text: "rem".into(),
+                    kind: Kind::Comment
+                },
+                Issue::UnreachableKeyword { text: "x1".into() },
+                Issue::DeadMerge {
+                    into: BaseKind::Symbol,
+                    from: BaseKind::Word
+                },
+            ]
+        );
+        let cfg = cfg.merge(BaseKind::Word, BaseKind::Number);
+        assert!(!cfg
+            .validate(&Base)
+            .issues
+            .contains(&Issue::UnreachableKeyword { text: "x1".into() }));
+    }
+
+    struct Quoting;
+    impl ContextClassifier for Quoting {
+        type Custom = Kind;
+        type State = bool;
+        type Snapshot = bool;
+
+        fn start(&self) -> bool {
+            false
+        }
+
+        fn classify_at(
+            &self,
+            quoted: &mut bool,
+            _: Option<char>,
+            c: char,
+            _: &str,
+        ) -> Classification<Kind> {
+            let kind = match *quoted {
+                true => (
+                    BaseKind::Word,
+                    Some(Kind::Comment),
+                    Some(Cow::Borrowed("'")),
+                ),
+                false => (BaseKind::Word, None, None),
+            };
+            *quoted ^= c == '"';
+            kind
+        }
+
+        fn snapshot(&self, quoted: &bool) -> bool {
+            *quoted
+        }
+
+        fn restore(&self, quoted: &mut bool, snapshot: bool) {
+            *quoted = snapshot;
+        }
+    }
+
+    #[test]
+    fn test_sweep_starts_from_fresh_state() {
+        let cfg = Config::default()
+            .skip_custom([Kind::Comment])
+            .keywords([("let", Kind::Keyword)]);
+        assert!(cfg.validate(&Quoting).is_clean());
+    }
+}
@@ -1,3 +1,5 @@
+extern crate self as xlex_lexer;
+
 pub mod lexer;
 
 pub fn add(left: u64, right: u64) -> u64 {